@@ -1,41 +1,207 @@
 use uuid::Uuid;
+use crate::dice::{self, DiceRoll};
 use crate::encounters::Character;
 use crate::encounters::character::{UpdateCharacterCommand, CharacterCommandResponse};
-use crate::encounters::encounter::EncounterManagerState;
+use crate::encounters::combat_log::{to_markdown, CombatLogCommands, CombatLogResponse};
+use crate::encounters::encounter::{EncounterChangeMessages, EncounterManagerState};
 use crate::services::{
     file_system_connection::FileSystemState,
     files::file_structure::{FsCommand, QueryCommandResponse},
 };
 use crate::services::files::file_structure::TouchCommand;
-use crate::encounters::commands::{AddCharacterCommand, EncounterCommandResponse, EncounterCommands, UpdateStageCommand};
+use crate::services::storage::{AutosaveState, CheckpointCommandResponse, CheckpointCommands, Checkpoints, ConflictCommandResponse, ConflictCommands, EncounterFileEvent};
+use crate::encounters::commands::{AddCharacterCommand, AddInitiativeEntryCommand, EncounterCommandResponse, EncounterCommands, RollInitiativeCommand, UpdateStageCommand};
 
 #[tauri::command]
 #[specta::specta]
-pub async fn encounter(state: EncounterManagerState<'_>, command: EncounterCommands) -> Result<EncounterCommandResponse, String> {
+pub async fn encounter(state: EncounterManagerState<'_>, autosave: AutosaveState<'_>, command: EncounterCommands) -> Result<EncounterCommandResponse, String> {
     let mut collection = state.lock().await;
     match command {
         EncounterCommands::ListEncounter => EncounterCommandResponse::list_from_collection(&collection),
         EncounterCommands::UpdateStage(UpdateStageCommand { id, stage }) => {
             let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
-            EncounterCommandResponse::from_stage_command(encounter, stage)
+            let response = EncounterCommandResponse::from_stage_command(encounter, stage)?;
+            autosave.record(encounter)?;
+            Ok(response)
         },
         EncounterCommands::AddCharacter(AddCharacterCommand { id, character }) => {
             let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
             let messages = character.validation_messages();
             if messages.is_empty() {
-                encounter.add_character(character);
+                encounter.add_character(*character);
+                autosave.record(encounter)?;
             }
             Ok(EncounterCommandResponse::character_added(encounter, &messages))
         },
+        EncounterCommands::RollInitiative(RollInitiativeCommand { id, options }) => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            let response = EncounterCommandResponse::from_roll_initiative(encounter, &options, &mut rand::thread_rng())?;
+            autosave.record(encounter)?;
+            Ok(response)
+        },
+        EncounterCommands::UpdateTieBreak { id, policy } => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            let response = EncounterCommandResponse::from_tie_break(encounter, policy);
+            autosave.record(encounter)?;
+            Ok(response)
+        },
+        EncounterCommands::RemoveCharacter { id, character } => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            encounter.remove_character(character)?;
+            autosave.record(encounter)?;
+            Ok(EncounterCommandResponse::EncounterChanged(encounter.clone()))
+        },
+        EncounterCommands::DuplicateCharacter { id, character } => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            encounter.duplicate_character(character)?;
+            autosave.record(encounter)?;
+            Ok(EncounterCommandResponse::EncounterChanged(encounter.clone()))
+        },
+        EncounterCommands::MoveCharacter { id, character, index } => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            encounter.move_character(character, index as usize)?;
+            autosave.record(encounter)?;
+            Ok(EncounterCommandResponse::EncounterChanged(encounter.clone()))
+        },
+        EncounterCommands::CreateEncounter { name } => {
+            let messages = collection.validate_name(&name, None);
+            if messages.is_empty() {
+                let encounter = collection.create_encounter(name);
+                autosave.record(encounter)?;
+            }
+            Ok(EncounterCommandResponse::list_changed(&collection, &messages))
+        },
+        EncounterCommands::RenameEncounter { id, name } => {
+            collection.find_encounter(id).ok_or("Encounter not found")?;
+            let messages = collection.validate_name(&name, Some(id));
+            if messages.is_empty() {
+                let encounter = collection.rename_encounter(id, name)?;
+                autosave.record(encounter)?;
+            }
+            Ok(EncounterCommandResponse::list_changed(&collection, &messages))
+        },
+        EncounterCommands::CloneEncounter { id } => {
+            let encounter = collection.clone_encounter(id)?;
+            autosave.record(encounter)?;
+            Ok(EncounterCommandResponse::list_changed(&collection, &EncounterChangeMessages::none()))
+        },
+        EncounterCommands::DeleteEncounter { id } => {
+            collection.delete_encounter(id)?;
+            autosave.record_deletion(id)?;
+            Checkpoints::new(autosave.store().directory()).delete(id)?;
+            Ok(EncounterCommandResponse::list_changed(&collection, &EncounterChangeMessages::none()))
+        },
+        EncounterCommands::GroupCharacters { id, name, characters } => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            encounter.group_characters(name, &characters)?;
+            autosave.record(encounter)?;
+            Ok(EncounterCommandResponse::EncounterChanged(encounter.clone()))
+        },
+        EncounterCommands::UngroupCharacters { id, group } => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            encounter.ungroup_characters(group)?;
+            autosave.record(encounter)?;
+            Ok(EncounterCommandResponse::EncounterChanged(encounter.clone()))
+        },
+        EncounterCommands::AddInitiativeEntry(AddInitiativeEntryCommand { id, name, character, initiative }) => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            encounter.add_initiative_entry(name, character, initiative)?;
+            autosave.record(encounter)?;
+            Ok(EncounterCommandResponse::EncounterChanged(encounter.clone()))
+        },
+        EncounterCommands::RemoveInitiativeEntry { id, entry } => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            encounter.remove_initiative_entry(entry)?;
+            autosave.record(encounter)?;
+            Ok(EncounterCommandResponse::EncounterChanged(encounter.clone()))
+        },
+        EncounterCommands::ResolveConcentrationCheck { id, check, passed } => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            encounter.resolve_concentration_check(check, passed)?;
+            autosave.record(encounter)?;
+            Ok(EncounterCommandResponse::EncounterChanged(encounter.clone()))
+        },
+        EncounterCommands::Undo { id } => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            let response = EncounterCommandResponse::from_undo(encounter)?;
+            autosave.record(encounter)?;
+            Ok(response)
+        },
+        EncounterCommands::Redo { id } => {
+            let encounter = collection.find_encounter_mut(id).ok_or("Encounter not found")?;
+            let response = EncounterCommandResponse::from_redo(encounter)?;
+            autosave.record(encounter)?;
+            Ok(response)
+        },
     }
 }
 
 #[tauri::command]
 #[specta::specta]
-pub async fn update_encounter_character(state: EncounterManagerState<'_>, encounter_id: Uuid, command: UpdateCharacterCommand) -> Result<CharacterCommandResponse, String> {
+pub async fn update_encounter_character(state: EncounterManagerState<'_>, autosave: AutosaveState<'_>, encounter_id: Uuid, command: UpdateCharacterCommand) -> Result<CharacterCommandResponse, String> {
     let mut manager = state.lock().await;
     let encounter = manager.find_encounter_mut(encounter_id).ok_or("Encounter not found")?;
-    encounter.update_character(command)
+    let response = encounter.update_character(command)?;
+    autosave.record(encounter)?;
+    Ok(response)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn encounter_conflict(state: EncounterManagerState<'_>, autosave: AutosaveState<'_>, command: ConflictCommands) -> Result<ConflictCommandResponse, String> {
+    let mut collection = state.lock().await;
+    match command {
+        ConflictCommands::ListConflicts => Ok(ConflictCommandResponse::ConflictList(autosave.conflicts())),
+        ConflictCommands::Resolve { id, resolution } => {
+            let encounter = autosave.resolve_conflict(&mut collection, id, &resolution)?;
+            Ok(ConflictCommandResponse::Resolved(Box::new(encounter)))
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn encounter_startup_events(autosave: AutosaveState<'_>) -> Result<Vec<EncounterFileEvent>, String> {
+    Ok(autosave.startup_events())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn encounter_checkpoint(state: EncounterManagerState<'_>, autosave: AutosaveState<'_>, command: CheckpointCommands) -> Result<CheckpointCommandResponse, String> {
+    let mut collection = state.lock().await;
+    let checkpoints = Checkpoints::new(autosave.store().directory());
+    match command {
+        CheckpointCommands::Create { id, label } => {
+            let encounter = collection.find_encounter(id).ok_or("Encounter not found")?;
+            checkpoints.create(encounter, label).map(CheckpointCommandResponse::Created)
+        }
+        CheckpointCommands::List { id } => checkpoints.list(id).map(CheckpointCommandResponse::CheckpointList),
+        CheckpointCommands::Restore { id, checkpoint_id } => {
+            checkpoints.restore(&mut collection, &autosave, id, checkpoint_id).map(CheckpointCommandResponse::Restored)
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn encounter_log(state: EncounterManagerState<'_>, command: CombatLogCommands) -> Result<CombatLogResponse, String> {
+    let collection = state.lock().await;
+    match command {
+        CombatLogCommands::List { id } => {
+            let encounter = collection.find_encounter(id).ok_or("Encounter not found")?;
+            Ok(CombatLogResponse::Entries(encounter.log().to_vec()))
+        }
+        CombatLogCommands::ExportMarkdown { id } => {
+            let encounter = collection.find_encounter(id).ok_or("Encounter not found")?;
+            Ok(CombatLogResponse::Markdown(to_markdown(encounter)))
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub async fn roll_dice(expression: String) -> Result<DiceRoll, String> {
+    dice::roll(&expression, &mut rand::thread_rng())
 }
 
 #[tauri::command]
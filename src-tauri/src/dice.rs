@@ -0,0 +1,305 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+const MAX_DICE: u32 = 100;
+const MAX_SIDES: u32 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Keep {
+    Highest(u32),
+    Lowest(u32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TermKind {
+    Dice { count: u32, sides: u32, keep: Option<Keep>, reroll: Option<u32> },
+    Constant(i32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Term {
+    negative: bool,
+    kind: TermKind,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiceExpression {
+    terms: Vec<Term>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DieRoll {
+    pub value: u32,
+    pub rerolled: Option<u32>,
+    pub kept: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiceGroup {
+    pub notation: String,
+    pub negative: bool,
+    pub rolls: Vec<DieRoll>,
+    pub subtotal: i32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DiceRoll {
+    pub expression: String,
+    pub groups: Vec<DiceGroup>,
+    pub modifier: i32,
+    pub total: i32,
+}
+
+impl DiceExpression {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = expression.trim().to_lowercase();
+        let (dice, advantage) = match expression.rsplit_once(char::is_whitespace) {
+            Some((dice, "adv")) => (dice, Some(Keep::Highest(1))),
+            Some((dice, "dis")) => (dice, Some(Keep::Lowest(1))),
+            _ => (expression.as_str(), None),
+        };
+
+        let dice: String = dice.chars().filter(|c| !c.is_whitespace()).collect();
+        if dice.is_empty() {
+            return Err(String::from("Dice expression cannot be empty"));
+        }
+
+        let mut terms = Vec::new();
+        let mut negative = false;
+        let mut start = 0;
+        for (index, c) in dice.char_indices() {
+            if (c == '+' || c == '-') && index > start {
+                terms.push(Term { negative, kind: parse_term(&dice[start..index])? });
+                negative = c == '-';
+                start = index + 1;
+            } else if c == '+' || c == '-' {
+                if index > 0 {
+                    return Err(format!("Unexpected \"{}\" in dice expression", c));
+                }
+                negative = c == '-';
+                start = index + 1;
+            }
+        }
+        terms.push(Term { negative, kind: parse_term(&dice[start..])? });
+
+        if let Some(keep) = advantage {
+            let mut dice_terms = terms.iter_mut().filter(|term| matches!(term.kind, TermKind::Dice { .. }));
+            match (dice_terms.next(), dice_terms.next()) {
+                (Some(Term { kind: TermKind::Dice { count: count @ 1, keep: term_keep @ None, .. }, .. }), None) => {
+                    *count = 2;
+                    *term_keep = Some(keep);
+                }
+                _ => return Err(String::from("Advantage and disadvantage need a single die, like 1d20 adv")),
+            }
+        }
+        Ok(Self { terms })
+    }
+
+    pub fn roll<R: Rng>(&self, rng: &mut R) -> (Vec<DiceGroup>, i32) {
+        let mut groups = Vec::new();
+        let mut modifier = 0;
+        for term in &self.terms {
+            let sign = if term.negative { -1 } else { 1 };
+            match term.kind {
+                TermKind::Constant(value) => modifier += sign * value,
+                TermKind::Dice { count, sides, keep, reroll } => {
+                    let rolls = roll_dice(rng, count, sides, keep, reroll);
+                    let subtotal: u32 = rolls.iter().filter(|roll| roll.kept).map(|roll| roll.value).sum();
+                    groups.push(DiceGroup {
+                        notation: notation(count, sides, keep, reroll),
+                        negative: term.negative,
+                        rolls,
+                        subtotal: sign * subtotal as i32,
+                    });
+                }
+            }
+        }
+        (groups, modifier)
+    }
+}
+
+pub fn roll<R: Rng>(expression: &str, rng: &mut R) -> Result<DiceRoll, String> {
+    let (groups, modifier) = DiceExpression::parse(expression)?.roll(rng);
+    let total = groups.iter().map(|group| group.subtotal).sum::<i32>() + modifier;
+    Ok(DiceRoll { expression: expression.trim().to_string(), groups, modifier, total })
+}
+
+fn parse_term(term: &str) -> Result<TermKind, String> {
+    let Some((count, rest)) = term.split_once('d') else {
+        return parse_number(term, term).map(|value| TermKind::Constant(value as i32));
+    };
+
+    let count = match count {
+        "" => 1,
+        count => parse_number(count, term)?,
+    };
+    let sides_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    let sides = parse_number(&rest[..sides_end], term)?;
+    if !(1..=MAX_DICE).contains(&count) {
+        return Err(format!("\"{}\" must roll between 1 and {} dice", term, MAX_DICE));
+    }
+    if !(2..=MAX_SIDES).contains(&sides) {
+        return Err(format!("\"{}\" must use dice with between 2 and {} sides", term, MAX_SIDES));
+    }
+
+    let mut keep = None;
+    let mut reroll = None;
+    let mut options = &rest[sides_end..];
+    while !options.is_empty() {
+        let (option, rest) = if let Some(rest) = options.strip_prefix("kh") {
+            ("kh", rest)
+        } else if let Some(rest) = options.strip_prefix("kl") {
+            ("kl", rest)
+        } else if let Some(rest) = options.strip_prefix('r') {
+            ("r", rest)
+        } else {
+            return Err(format!("Unknown dice option \"{}\" in \"{}\"", options, term));
+        };
+        let value_end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let value = parse_number(&rest[..value_end], term)?;
+        options = &rest[value_end..];
+
+        match option {
+            "r" if value >= sides => return Err(format!("\"{}\" would reroll every result", term)),
+            "r" => reroll = Some(value),
+            _ if value == 0 || value > count => {
+                return Err(format!("\"{}\" must keep between 1 and {} dice", term, count));
+            }
+            "kh" => keep = Some(Keep::Highest(value)),
+            _ => keep = Some(Keep::Lowest(value)),
+        }
+    }
+    Ok(TermKind::Dice { count, sides, keep, reroll })
+}
+
+fn parse_number(value: &str, term: &str) -> Result<u32, String> {
+    value.parse().map_err(|_| format!("\"{}\" is not valid dice notation", term))
+}
+
+// Rerolls once, and the new result stands
+fn roll_dice<R: Rng>(rng: &mut R, count: u32, sides: u32, keep: Option<Keep>, reroll: Option<u32>) -> Vec<DieRoll> {
+    let mut rolls: Vec<DieRoll> = (0..count)
+        .map(|_| {
+            let value = rng.gen_range(1..=sides);
+            match reroll {
+                Some(reroll) if value <= reroll => DieRoll { value: rng.gen_range(1..=sides), rerolled: Some(value), kept: true },
+                _ => DieRoll { value, rerolled: None, kept: true },
+            }
+        })
+        .collect();
+
+    if let Some(keep) = keep {
+        let mut order: Vec<usize> = (0..rolls.len()).collect();
+        order.sort_by_key(|&index| rolls[index].value);
+        let dropped = match keep {
+            Keep::Highest(kept) => &order[..(count - kept) as usize],
+            Keep::Lowest(kept) => &order[kept as usize..],
+        };
+        for &index in dropped {
+            rolls[index].kept = false;
+        }
+    }
+    rolls
+}
+
+fn notation(count: u32, sides: u32, keep: Option<Keep>, reroll: Option<u32>) -> String {
+    let mut notation = format!("{}d{}", count, sides);
+    match keep {
+        Some(Keep::Highest(kept)) => notation.push_str(&format!("kh{}", kept)),
+        Some(Keep::Lowest(kept)) => notation.push_str(&format!("kl{}", kept)),
+        None => {}
+    }
+    if let Some(reroll) = reroll {
+        notation.push_str(&format!("r{}", reroll));
+    }
+    notation
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let expression = DiceExpression::parse("2d6 + 3").unwrap();
+        assert_eq!(expression.terms, vec![
+            Term { negative: false, kind: TermKind::Dice { count: 2, sides: 6, keep: None, reroll: None } },
+            Term { negative: false, kind: TermKind::Constant(3) },
+        ]);
+
+        let expression = DiceExpression::parse("d20-1").unwrap();
+        assert_eq!(expression.terms[1], Term { negative: true, kind: TermKind::Constant(1) });
+
+        let expression = DiceExpression::parse("1d20 adv").unwrap();
+        assert_eq!(expression.terms[0].kind, TermKind::Dice { count: 2, sides: 20, keep: Some(Keep::Highest(1)), reroll: None });
+
+        let expression = DiceExpression::parse("4d6kh3").unwrap();
+        assert_eq!(expression.terms[0].kind, TermKind::Dice { count: 4, sides: 6, keep: Some(Keep::Highest(3)), reroll: None });
+
+        let expression = DiceExpression::parse("1d8r1").unwrap();
+        assert_eq!(expression.terms[0].kind, TermKind::Dice { count: 1, sides: 8, keep: None, reroll: Some(1) });
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(DiceExpression::parse(" ").err(), Some(String::from("Dice expression cannot be empty")));
+        assert_eq!(DiceExpression::parse("2d").err(), Some(String::from("\"2d\" is not valid dice notation")));
+        assert_eq!(DiceExpression::parse("2d6++1").err(), Some(String::from("Unexpected \"+\" in dice expression")));
+        assert_eq!(DiceExpression::parse("4d6kh5").err(), Some(String::from("\"4d6kh5\" must keep between 1 and 4 dice")));
+        assert_eq!(DiceExpression::parse("1d6x").err(), Some(String::from("Unknown dice option \"x\" in \"1d6x\"")));
+        assert_eq!(DiceExpression::parse("1d6r6").err(), Some(String::from("\"1d6r6\" would reroll every result")));
+        assert_eq!(DiceExpression::parse("1000d6").err(), Some(String::from("\"1000d6\" must roll between 1 and 100 dice")));
+        assert_eq!(
+            DiceExpression::parse("2d20 adv").err(),
+            Some(String::from("Advantage and disadvantage need a single die, like 1d20 adv"))
+        );
+    }
+
+    #[test]
+    fn test_roll_is_deterministic_with_a_seed() {
+        let first = roll("2d6+3", &mut StdRng::seed_from_u64(7)).unwrap();
+        let second = roll("2d6+3", &mut StdRng::seed_from_u64(7)).unwrap();
+        assert_eq!(first, second);
+
+        assert_eq!(first.groups.len(), 1);
+        assert_eq!(first.groups[0].notation, "2d6");
+        assert_eq!(first.groups[0].rolls.len(), 2);
+        assert_eq!(first.modifier, 3);
+        let rolled: u32 = first.groups[0].rolls.iter().map(|roll| roll.value).sum();
+        assert_eq!(first.total, rolled as i32 + 3);
+    }
+
+    #[test]
+    fn test_keep_highest_and_advantage() {
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..20 {
+            let result = roll("4d6kh3", &mut rng).unwrap();
+            let mut values: Vec<u32> = result.groups[0].rolls.iter().map(|roll| roll.value).collect();
+            values.sort();
+            assert_eq!(result.groups[0].rolls.iter().filter(|roll| roll.kept).count(), 3);
+            assert_eq!(result.total, values[1..].iter().sum::<u32>() as i32);
+
+            let result = roll("1d20 dis", &mut rng).unwrap();
+            let lowest = result.groups[0].rolls.iter().map(|roll| roll.value).min().unwrap();
+            assert_eq!(result.groups[0].rolls.len(), 2);
+            assert_eq!(result.total, lowest as i32);
+        }
+    }
+
+    #[test]
+    fn test_reroll() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let result = roll("100d8r1", &mut rng).unwrap();
+        let rolls = &result.groups[0].rolls;
+        assert!(rolls.iter().any(|roll| roll.rerolled.is_some()));
+        assert!(rolls.iter().all(|roll| roll.rerolled.is_none() || roll.rerolled == Some(1)));
+        assert!(rolls.iter().filter(|roll| roll.rerolled.is_none()).all(|roll| roll.value > 1));
+    }
+}
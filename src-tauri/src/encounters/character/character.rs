@@ -1,9 +1,11 @@
 use std::cmp::Ordering;
+use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use uuid::Uuid;
 
+use crate::encounters::character::{Ability, AbilityScores, Condition, DamageBreakdown, DamageType};
 use crate::services::FrontendMessage;
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Type)]
@@ -61,6 +63,9 @@ pub struct CharacterChangeMessages {
     pub name: Vec<FrontendMessage>,
     pub initiative: Vec<FrontendMessage>,
     pub hp: CharacterHpMessages,
+    pub armor_class: Vec<FrontendMessage>,
+    pub ability_scores: Vec<FrontendMessage>,
+    pub proficiency_bonus: Vec<FrontendMessage>,
 }
 
 impl CharacterChangeMessages {
@@ -80,11 +85,74 @@ impl CharacterChangeMessages {
         }
     }
 
+    pub fn with_armor_class_error_message<T: Into<String>>(mut self, message: T) -> Self {
+        add_error_message(&mut self.armor_class, message);
+        self
+    }
+
+    pub fn with_ability_score_error_message<T: Into<String>>(mut self, message: T) -> Self {
+        add_error_message(&mut self.ability_scores, message);
+        self
+    }
+
+    pub fn with_proficiency_bonus_error_message<T: Into<String>>(mut self, message: T) -> Self {
+        add_error_message(&mut self.proficiency_bonus, message);
+        self
+    }
+
+    pub fn validate_stats(&mut self, character: &Character) {
+        if let Err(message) = check_armor_class(character.armor_class) {
+            add_error_message(&mut self.armor_class, message);
+        }
+        for ability in Ability::ALL {
+            if let Err(message) = check_ability_score(ability, character.ability_scores.score(ability)) {
+                add_error_message(&mut self.ability_scores, message);
+            }
+        }
+        if let Err(message) = check_proficiency_bonus(character.proficiency_bonus) {
+            add_error_message(&mut self.proficiency_bonus, message);
+        }
+    }
+
     pub fn is_empty(&self) -> bool {
-        self.name.is_empty() && self.initiative.is_empty() && self.hp.is_empty()
+        self.name.is_empty()
+            && self.initiative.is_empty()
+            && self.hp.is_empty()
+            && self.armor_class.is_empty()
+            && self.ability_scores.is_empty()
+            && self.proficiency_bonus.is_empty()
     }
 }
 
+fn add_error_message<T: Into<String>>(messages: &mut Vec<FrontendMessage>, message: T) {
+    let message = FrontendMessage::error(message);
+    if !messages.contains(&message) {
+        messages.push(message);
+    }
+}
+
+fn check_armor_class(armor_class: i32) -> Result<(), String> {
+    if armor_class < 1 {
+        return Err(String::from("Armor Class cannot be less than 1"));
+    }
+    Ok(())
+}
+
+fn check_ability_score(ability: Ability, score: i32) -> Result<(), String> {
+    if !(1..=30).contains(&score) {
+        return Err(format!("{} must be between 1 and 30", ability.name()));
+    }
+    Ok(())
+}
+
+// Proficiency runs from +2 for new characters and weak monsters up to +9 for CR 30
+fn check_proficiency_bonus(bonus: i32) -> Result<(), String> {
+    if !(2..=9).contains(&bonus) {
+        return Err(String::from("Proficiency bonus must be between 2 and 9"));
+    }
+    Ok(())
+}
+
 
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Type)]
 #[serde(rename_all = "camelCase")]
@@ -94,14 +162,73 @@ pub struct HitPoints {
     pub temporary: i32,
 }
 
+// Monsters are simply down at 0 HP unless massive damage kills them
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, Eq, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum LifeState {
+    #[default]
+    Alive,
+    Dying,
+    Stable,
+    Dead,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct LifeStateChange {
+    pub id: Uuid,
+    pub name: String,
+    pub from: LifeState,
+    pub to: LifeState,
+}
+
+impl LifeStateChange {
+    pub fn between(before: &Character, after: &Character) -> Option<Self> {
+        if before.life_state == after.life_state {
+            return None;
+        }
+        Some(Self {
+            id: after.uuid(),
+            name: after.name.clone(),
+            from: before.life_state,
+            to: after.life_state,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, Eq, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DeathSaves {
+    pub successes: u32,
+    pub failures: u32,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct Character {
     id: Uuid,
     pub name: String,
     pub hp: HitPoints,
+    pub armor_class: i32,
     pub initiative: i32,
+    // 0 is a valid initiative, so this tells a rolled one from an unset one
+    pub initiative_set: bool,
     pub initiative_modifier: i32,
+    pub player: bool,
+    pub ability_scores: AbilityScores,
+    pub proficiency_bonus: i32,
+    pub save_proficiencies: Vec<Ability>,
+    pub tie_breaker: Option<i32>,
+    // Only matters while the encounter is in its surprise round
+    pub surprised: bool,
+    pub skip_turn: bool,
+    pub conditions: Vec<Condition>,
+    pub life_state: LifeState,
+    pub death_saves: DeathSaves,
+    pub resistances: Vec<DamageType>,
+    pub vulnerabilities: Vec<DamageType>,
+    pub immunities: Vec<DamageType>,
+    pub concentration: Option<String>,
 }
 
 impl Character {
@@ -115,8 +242,25 @@ impl Character {
             id: Uuid::new_v4(),
             name: name.into(),
             hp,
+            armor_class: 10,
             initiative,
+            // Freshly created characters pass 0 for an initiative they have not rolled yet
+            initiative_set: initiative != 0,
             initiative_modifier: 0,
+            player: false,
+            ability_scores: AbilityScores::default(),
+            proficiency_bonus: 2,
+            save_proficiencies: Vec::new(),
+            tie_breaker: None,
+            surprised: false,
+            skip_turn: false,
+            conditions: Vec::new(),
+            life_state: LifeState::Alive,
+            death_saves: DeathSaves::default(),
+            resistances: Vec::new(),
+            vulnerabilities: Vec::new(),
+            immunities: Vec::new(),
+            concentration: None,
         }
     }
 
@@ -128,6 +272,18 @@ impl Character {
         self.id
     }
 
+    pub fn duplicate<T: Into<String>>(&self, name: T) -> Character {
+        Character {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            ..self.clone()
+        }
+    }
+
+    pub fn remap_conditions(&mut self, ids: &HashMap<Uuid, Uuid>) {
+        self.conditions.retain_mut(|condition| condition.remap(ids));
+    }
+
     pub fn is_same_as(&self, other: &Character) -> bool {
         self.id() == other.id()
     }
@@ -142,11 +298,42 @@ impl Character {
         Ok(())
     }
 
+    // The dead stay at 0 HP until revived
     pub fn heal(&mut self, value: i32) {
+        if self.life_state == LifeState::Dead {
+            return;
+        }
         self.set_current_hp(self.hp.current + value);
+        if self.hp.current > 0 {
+            self.life_state = LifeState::Alive;
+            self.death_saves = DeathSaves::default();
+        }
+    }
+
+    pub fn damage(&mut self, value: i32, damage_type: Option<DamageType>) -> DamageBreakdown {
+        self.take_damage(value, damage_type, false)
+    }
+
+    pub fn critical_damage(&mut self, value: i32, damage_type: Option<DamageType>) -> DamageBreakdown {
+        self.take_damage(value, damage_type, true)
+    }
+
+    pub fn assess_damage(&self, value: i32, damage_type: Option<DamageType>) -> DamageBreakdown {
+        let has = |types: &[DamageType]| damage_type.is_some_and(|t| types.contains(&t));
+        DamageBreakdown::new(self.id, value, damage_type, has(&self.immunities), has(&self.resistances), has(&self.vulnerabilities))
     }
 
-    pub fn damage(&mut self, value: i32) {
+    fn take_damage(&mut self, value: i32, damage_type: Option<DamageType>, critical: bool) -> DamageBreakdown {
+        let mut breakdown = self.assess_damage(value, damage_type);
+        let before = self.hp;
+        self.lose_hp(breakdown.adjusted, critical);
+        breakdown.absorbed = before.temporary - self.hp.temporary;
+        breakdown.taken = before.current - self.hp.current;
+        breakdown
+    }
+
+    // Leftover damage that reaches the HP maximum kills outright
+    fn lose_hp(&mut self, value: i32, critical: bool) {
         if self.hp.temporary > 0 {
             let change = self.hp.temporary - value;
             if change > 0 {
@@ -154,11 +341,76 @@ impl Character {
                 return;
             } else {
                 self.set_temporary_hp(0);
-                self.damage(-change);
+                self.lose_hp(-change, critical);
                 return;
             }
         }
+        let excess = value - self.hp.current;
         self.set_current_hp(self.hp.current - value);
+        if value <= 0 || self.hp.current > 0 {
+            return;
+        }
+        // Falling unconscious breaks concentration
+        self.concentration = None;
+        if excess >= self.hp.total {
+            self.life_state = LifeState::Dead;
+        } else if self.player && self.life_state == LifeState::Alive {
+            self.life_state = LifeState::Dying;
+        } else if self.player {
+            self.fail_death_saves(if critical { 2 } else { 1 });
+        }
+    }
+
+    pub fn roll_death_save(&mut self, roll: i32) -> Result<(), String> {
+        if self.life_state != LifeState::Dying {
+            return Err(String::from("Character is not dying"));
+        }
+        match roll {
+            20 => self.heal(1),
+            10..=19 => {
+                self.death_saves.successes += 1;
+                if self.death_saves.successes >= 3 {
+                    self.stabilize()?;
+                }
+            }
+            2..=9 => self.fail_death_saves(1),
+            1 => self.fail_death_saves(2),
+            _ => return Err(String::from("Death save roll must be between 1 and 20")),
+        }
+        Ok(())
+    }
+
+    pub fn stabilize(&mut self) -> Result<(), String> {
+        if self.life_state != LifeState::Dying {
+            return Err(String::from("Character is not dying"));
+        }
+        self.life_state = LifeState::Stable;
+        self.death_saves = DeathSaves::default();
+        Ok(())
+    }
+
+    pub fn revive(&mut self, hp: i32) -> Result<(), String> {
+        if self.life_state != LifeState::Dead {
+            return Err(String::from("Character is not dead"));
+        }
+        self.life_state = LifeState::Alive;
+        self.death_saves = DeathSaves::default();
+        self.set_current_hp(hp.max(1));
+        Ok(())
+    }
+
+    fn fail_death_saves(&mut self, failures: u32) {
+        // A stable character that takes damage starts dying again
+        if self.life_state == LifeState::Stable {
+            self.life_state = LifeState::Dying;
+        }
+        if self.life_state != LifeState::Dying {
+            return;
+        }
+        self.death_saves.failures += failures;
+        if self.death_saves.failures >= 3 {
+            self.life_state = LifeState::Dead;
+        }
     }
 
     pub fn set_total_hp(&mut self, value: i32) {
@@ -177,22 +429,96 @@ impl Character {
 
     pub fn set_initiative(&mut self, value: i32) {
         self.initiative = value.max(-20);
+        self.initiative_set = true;
     }
 
     pub fn set_initiative_modifier(&mut self, value: i32) {
         self.initiative_modifier = value.max(-20);
     }
 
+    pub fn add_condition(&mut self, condition: Condition) {
+        self.conditions.push(condition);
+    }
+
+    pub fn remove_condition(&mut self, id: Uuid) -> Result<Condition, String> {
+        let index = self.conditions.iter().position(|c| c.id == id).ok_or("Condition not found")?;
+        Ok(self.conditions.remove(index))
+    }
+
+    pub fn set_resistances(&mut self, types: Vec<DamageType>) {
+        self.resistances = damage_type_set(types);
+    }
+
+    pub fn set_vulnerabilities(&mut self, types: Vec<DamageType>) {
+        self.vulnerabilities = damage_type_set(types);
+    }
+
+    pub fn set_immunities(&mut self, types: Vec<DamageType>) {
+        self.immunities = damage_type_set(types);
+    }
+
+    pub fn set_concentration(&mut self, spell: Option<String>) -> Result<(), String> {
+        if spell.as_ref().is_some_and(|spell| spell.trim().is_empty()) {
+            return Err(String::from("Spell cannot be empty"));
+        }
+        self.concentration = spell;
+        Ok(())
+    }
+
+    pub fn set_armor_class(&mut self, armor_class: i32) -> Result<(), String> {
+        check_armor_class(armor_class)?;
+        self.armor_class = armor_class;
+        Ok(())
+    }
+
+    pub fn set_ability_score(&mut self, ability: Ability, score: i32) -> Result<(), String> {
+        check_ability_score(ability, score)?;
+        *self.ability_scores.score_mut(ability) = score;
+        Ok(())
+    }
+
+    pub fn set_proficiency_bonus(&mut self, bonus: i32) -> Result<(), String> {
+        check_proficiency_bonus(bonus)?;
+        self.proficiency_bonus = bonus;
+        Ok(())
+    }
+
+    pub fn set_save_proficiencies(&mut self, mut abilities: Vec<Ability>) {
+        abilities.sort();
+        abilities.dedup();
+        self.save_proficiencies = abilities;
+    }
+
+    pub fn saving_throw(&self, ability: Ability) -> i32 {
+        let proficiency = if self.save_proficiencies.contains(&ability) { self.proficiency_bonus } else { 0 };
+        self.ability_scores.modifier(ability) + proficiency
+    }
+
+    // Dying characters keep their turns to roll death saves
+    pub fn is_down(&self) -> bool {
+        self.hp.current <= 0 && self.life_state != LifeState::Dying
+    }
+
+    pub fn has_initiative(&self) -> bool {
+        self.initiative_set
+    }
+
     pub fn validation_messages(&self) -> CharacterChangeMessages {
         let mut messages = CharacterChangeMessages::none();
         if self.name.is_empty() {
             messages.add_name_error_message("Name cannot be empty");
         }
         messages.hp.validate_hp(&self.hp);
+        messages.validate_stats(self);
         messages
     }
 }
 
+fn damage_type_set(mut types: Vec<DamageType>) -> Vec<DamageType> {
+    types.sort();
+    types.dedup();
+    types
+}
 
 impl PartialOrd for Character {
     fn partial_cmp(&self, other: &Character) -> Option<std::cmp::Ordering> {
@@ -217,7 +543,7 @@ impl Ord for Character {
 
 #[cfg(test)]
 mod tests {
-    use crate::encounters::character::{Character, CharacterChangeMessages, HitPoints};
+    use crate::encounters::character::{Ability, AbilityScores, Character, CharacterChangeMessages, DamageType, DeathSaves, HitPoints, LifeState};
     use crate::services::FrontendMessage;
 
     #[test]
@@ -232,6 +558,7 @@ mod tests {
         assert_eq!(character.hp.temporary, 0);
         assert_eq!(character.initiative, initiative);
         assert_eq!(character.initiative_modifier, 0);
+        assert_eq!(character.player, false);
         assert_eq!(character.id().len(), 36);
     }
 
@@ -282,9 +609,9 @@ mod tests {
         let total_hp = 10;
         let mut character_a = Character::new("character a", total_hp, 10);
 
-        character_a.damage(5);
+        character_a.damage(5, None);
         assert_eq!(character_a.hp.current, 5);
-        character_a.damage(10);
+        character_a.damage(10, None);
         assert_eq!(character_a.hp.current, 0);
 
         character_a.heal(5);
@@ -296,15 +623,187 @@ mod tests {
         assert_eq!(character_a.hp.current, 10);
         assert_eq!(character_a.hp.temporary, 10);
 
-        character_a.damage(5);
+        character_a.damage(5, None);
         assert_eq!(character_a.hp.current, 10);
         assert_eq!(character_a.hp.temporary, 5);
 
-        character_a.damage(10);
+        character_a.damage(10, None);
         assert_eq!(character_a.hp.current, 5);
         assert_eq!(character_a.hp.temporary, 0);
     }
 
+    #[test]
+    fn test_damage_types() {
+        let mut troll = Character::new("Troll", 84, 10);
+        troll.set_resistances(vec![DamageType::Slashing, DamageType::Slashing]);
+        troll.set_vulnerabilities(vec![DamageType::Fire]);
+        troll.set_immunities(vec![DamageType::Poison]);
+        troll.set_temporary_hp(5);
+        assert_eq!(troll.resistances, vec![DamageType::Slashing]);
+
+        // Resistance halves the hit before temp HP soaks up what is left
+        let breakdown = troll.damage(13, Some(DamageType::Slashing));
+        assert!(breakdown.resisted);
+        assert_eq!((breakdown.adjusted, breakdown.absorbed, breakdown.taken), (6, 5, 1));
+        assert_eq!(troll.hp.current, 83);
+
+        let breakdown = troll.damage(10, Some(DamageType::Fire));
+        assert_eq!((breakdown.adjusted, breakdown.taken), (20, 20));
+
+        let breakdown = troll.damage(30, Some(DamageType::Poison));
+        assert!(breakdown.immune);
+        assert_eq!(breakdown.taken, 0);
+
+        let breakdown = troll.damage(7, None);
+        assert_eq!((breakdown.adjusted, breakdown.taken), (7, 7));
+        assert_eq!(troll.hp.current, 56);
+    }
+
+    #[test]
+    fn test_saving_throws() {
+        let mut gandalf = Character::new("Gandalf", 60, 15);
+        gandalf.set_ability_score(Ability::Constitution, 14).unwrap();
+        gandalf.set_ability_score(Ability::Wisdom, 18).unwrap();
+        gandalf.set_proficiency_bonus(4).unwrap();
+        gandalf.set_save_proficiencies(vec![Ability::Wisdom, Ability::Intelligence, Ability::Wisdom]);
+
+        assert_eq!(gandalf.save_proficiencies, vec![Ability::Intelligence, Ability::Wisdom]);
+        assert_eq!(gandalf.saving_throw(Ability::Constitution), 2);
+        assert_eq!(gandalf.saving_throw(Ability::Wisdom), 8);
+        assert_eq!(gandalf.saving_throw(Ability::Strength), 0);
+    }
+
+    #[test]
+    fn test_stat_setters_reject_invalid_values() {
+        let mut gandalf = Character::new("Gandalf", 60, 15);
+
+        assert_eq!(gandalf.set_armor_class(0), Err(String::from("Armor Class cannot be less than 1")));
+        assert_eq!(gandalf.set_ability_score(Ability::Strength, 31), Err(String::from("Strength must be between 1 and 30")));
+        assert_eq!(gandalf.set_proficiency_bonus(1), Err(String::from("Proficiency bonus must be between 2 and 9")));
+        assert_eq!(gandalf.armor_class, 10);
+        assert_eq!(gandalf.ability_scores, AbilityScores::default());
+        assert_eq!(gandalf.proficiency_bonus, 2);
+
+        gandalf.set_armor_class(15).unwrap();
+        assert_eq!(gandalf.armor_class, 15);
+    }
+
+    #[test]
+    fn test_dropping_to_zero_hp() {
+        let mut monster = Character::new("Goblin", 7, 10);
+        monster.damage(7, None);
+        assert_eq!(monster.life_state, LifeState::Alive);
+        assert!(monster.is_down());
+        monster.damage(3, None);
+        assert_eq!(monster.death_saves, DeathSaves::default());
+
+        let mut player = Character::new("Frodo", 8, 18);
+        player.player = true;
+        player.damage(8, None);
+        assert_eq!(player.life_state, LifeState::Dying);
+        assert!(!player.is_down(), "dying players still take their turn to roll death saves");
+
+        // Damage left over after 0 HP that reaches the HP maximum kills outright
+        let mut player = Character::new("Sam", 6, 19);
+        player.player = true;
+        player.damage(12, None);
+        assert_eq!(player.life_state, LifeState::Dead);
+    }
+
+    #[test]
+    fn test_damage_while_dying() {
+        let mut player = Character::new("Frodo", 8, 18);
+        player.player = true;
+        player.damage(8, None);
+
+        player.damage(1, None);
+        assert_eq!(player.death_saves, DeathSaves { successes: 0, failures: 1 });
+        player.critical_damage(1, None);
+        assert_eq!(player.life_state, LifeState::Dead);
+        assert_eq!(player.death_saves.failures, 3);
+    }
+
+    #[test]
+    fn test_death_saves() {
+        let mut player = Character::new("Frodo", 8, 18);
+        player.player = true;
+        assert_eq!(player.roll_death_save(12), Err(String::from("Character is not dying")));
+
+        player.damage(8, None);
+        player.roll_death_save(12).unwrap();
+        player.roll_death_save(1).unwrap();
+        assert_eq!(player.death_saves, DeathSaves { successes: 1, failures: 2 });
+        player.roll_death_save(10).unwrap();
+        player.roll_death_save(19).unwrap();
+        assert_eq!(player.life_state, LifeState::Stable);
+        assert_eq!(player.death_saves, DeathSaves::default());
+
+        // Stable characters start dying again when they take damage
+        player.damage(1, None);
+        assert_eq!(player.life_state, LifeState::Dying);
+        assert_eq!(player.death_saves.failures, 1);
+        assert!(player.roll_death_save(21).is_err());
+
+        player.roll_death_save(20).unwrap();
+        assert_eq!(player.life_state, LifeState::Alive);
+        assert_eq!(player.hp.current, 1);
+        assert_eq!(player.death_saves, DeathSaves::default());
+    }
+
+    #[test]
+    fn test_healing_resets_death_saves() {
+        let mut player = Character::new("Frodo", 8, 18);
+        player.player = true;
+        player.damage(8, None);
+        player.roll_death_save(5).unwrap();
+
+        player.heal(3);
+        assert_eq!(player.life_state, LifeState::Alive);
+        assert_eq!(player.death_saves, DeathSaves::default());
+        assert!(player.stabilize().is_err());
+    }
+
+    #[test]
+    fn test_the_dead_stay_dead() {
+        let mut monster = Character::new("Goblin", 7, 10);
+        monster.damage(14, None);
+        monster.heal(5);
+        assert_eq!(monster.life_state, LifeState::Dead);
+        assert_eq!(monster.hp.current, 0);
+
+        let mut player = Character::new("Frodo", 8, 18);
+        player.player = true;
+        player.damage(8, None);
+        player.roll_death_save(1).unwrap();
+        player.roll_death_save(1).unwrap();
+        assert_eq!(player.life_state, LifeState::Dead);
+        player.heal(8);
+        assert_eq!(player.life_state, LifeState::Dead);
+        assert_eq!(player.hp.current, 0);
+
+        assert!(Character::new("Sam", 6, 19).revive(3).is_err());
+        player.revive(3).unwrap();
+        assert_eq!(player.life_state, LifeState::Alive);
+        assert_eq!(player.hp.current, 3);
+        assert_eq!(player.death_saves, DeathSaves::default());
+    }
+
+    #[test]
+    fn test_setting_hp_leaves_life_state_alone() {
+        let mut monster = Character::new("Goblin", 7, 10);
+        monster.set_current_hp(0);
+        assert_eq!(monster.life_state, LifeState::Alive);
+        monster.set_current_hp(7);
+        assert_eq!(monster.hp.current, 7);
+
+        let mut player = Character::new("Frodo", 8, 18);
+        player.player = true;
+        player.set_concentration(Some(String::from("Bless"))).unwrap();
+        player.set_current_hp(0);
+        assert_eq!(player.life_state, LifeState::Alive);
+        assert_eq!(player.concentration, Some(String::from("Bless")));
+    }
+
     #[test]
     fn test_hp_values() {
         let total_hp = 10;
@@ -410,8 +909,24 @@ mod tests {
                 current: -1,
                 temporary: -1,
             },
+            armor_class: 10,
             initiative: 10,
+            initiative_set: true,
             initiative_modifier: 0,
+            player: false,
+            ability_scores: AbilityScores::default(),
+            proficiency_bonus: 2,
+            save_proficiencies: Vec::new(),
+            tie_breaker: None,
+            surprised: false,
+            skip_turn: false,
+            conditions: Vec::new(),
+            life_state: LifeState::Alive,
+            death_saves: DeathSaves::default(),
+            resistances: Vec::new(),
+            vulnerabilities: Vec::new(),
+            immunities: Vec::new(),
+            concentration: None,
         };
 
         let messages = character_a.validation_messages();
@@ -430,8 +945,24 @@ mod tests {
                 current: 15,
                 temporary: 0,
             },
+            armor_class: 10,
             initiative: 10,
+            initiative_set: true,
             initiative_modifier: 0,
+            player: false,
+            ability_scores: AbilityScores::default(),
+            proficiency_bonus: 2,
+            save_proficiencies: Vec::new(),
+            tie_breaker: None,
+            surprised: false,
+            skip_turn: false,
+            conditions: Vec::new(),
+            life_state: LifeState::Alive,
+            death_saves: DeathSaves::default(),
+            resistances: Vec::new(),
+            vulnerabilities: Vec::new(),
+            immunities: Vec::new(),
+            concentration: None,
         };
 
         let messages = character_a.validation_messages();
@@ -440,5 +971,20 @@ mod tests {
 
         assert!(hp.current.contains(&FrontendMessage::error("Current HP cannot be greater than Total HP")));
         assert!(hp.total.contains(&FrontendMessage::error("Total HP cannot be less than Current HP")));
+
+        let mut character_a = Character::new("character a", total_hp, 10);
+        character_a.armor_class = 0;
+        character_a.ability_scores.dexterity = 0;
+        character_a.ability_scores.charisma = 40;
+        character_a.proficiency_bonus = 12;
+
+        let messages = character_a.validation_messages();
+        assert!(!messages.is_empty());
+        assert_eq!(messages.armor_class, vec![FrontendMessage::error("Armor Class cannot be less than 1")]);
+        assert_eq!(messages.ability_scores, vec![
+            FrontendMessage::error("Dexterity must be between 1 and 30"),
+            FrontendMessage::error("Charisma must be between 1 and 30"),
+        ]);
+        assert_eq!(messages.proficiency_bonus, vec![FrontendMessage::error("Proficiency bonus must be between 2 and 9")]);
     }
 }
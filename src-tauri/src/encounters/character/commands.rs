@@ -3,7 +3,8 @@ use specta::Type;
 use uuid::Uuid;
 
 use crate::encounters::Character;
-use crate::encounters::character::CharacterChangeMessages;
+use crate::encounters::character::{Ability, CharacterChangeMessages, ConcentrationCheck, ConditionDuration, ConditionKind, DamageBreakdown, DamageType, LifeStateChange};
+use crate::encounters::initiative::CombatantGroup;
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
 #[serde(rename_all = "camelCase")]
@@ -15,7 +16,40 @@ pub enum UpdateCharacterCommand {
     UpdateTotalHp { id: Uuid, hp: i32 },
     UpdateTemporaryHp { id: Uuid, hp: i32 },
     Heal { id: Uuid, hp: i32 },
-    Damage { id: Uuid, hp: i32 },
+    Damage {
+        id: Uuid,
+        hp: i32,
+        #[serde(default)]
+        damage_type: Option<DamageType>,
+        #[serde(default)]
+        critical: bool,
+    },
+    UpdatePlayer { id: Uuid, player: bool },
+    UpdateTieBreaker { id: Uuid, roll: i32 },
+    UpdateSurprised { id: Uuid, surprised: bool },
+    UpdateSkipTurn { id: Uuid, skip: bool },
+    AddCondition {
+        id: Uuid,
+        kind: ConditionKind,
+        #[serde(default)]
+        duration: ConditionDuration,
+        #[serde(default)]
+        source: Option<Uuid>,
+        #[serde(default)]
+        concentration: bool,
+    },
+    RemoveCondition { id: Uuid, condition: Uuid },
+    RollDeathSave { id: Uuid, roll: i32 },
+    Stabilize { id: Uuid },
+    Revive { id: Uuid, hp: i32 },
+    UpdateResistances { id: Uuid, damage_types: Vec<DamageType> },
+    UpdateVulnerabilities { id: Uuid, damage_types: Vec<DamageType> },
+    UpdateImmunities { id: Uuid, damage_types: Vec<DamageType> },
+    UpdateConcentration { id: Uuid, spell: Option<String> },
+    UpdateArmorClass { id: Uuid, armor_class: i32 },
+    UpdateAbilityScore { id: Uuid, ability: Ability, score: i32 },
+    UpdateProficiencyBonus { id: Uuid, bonus: i32 },
+    UpdateSaveProficiencies { id: Uuid, abilities: Vec<Ability> },
 }
 
 impl UpdateCharacterCommand {
@@ -29,6 +63,23 @@ impl UpdateCharacterCommand {
             UpdateCharacterCommand::UpdateTemporaryHp { id, .. } => *id,
             UpdateCharacterCommand::Heal { id, .. } => *id,
             UpdateCharacterCommand::Damage { id, .. } => *id,
+            UpdateCharacterCommand::UpdatePlayer { id, .. } => *id,
+            UpdateCharacterCommand::UpdateTieBreaker { id, .. } => *id,
+            UpdateCharacterCommand::UpdateSurprised { id, .. } => *id,
+            UpdateCharacterCommand::UpdateSkipTurn { id, .. } => *id,
+            UpdateCharacterCommand::AddCondition { id, .. } => *id,
+            UpdateCharacterCommand::RemoveCondition { id, .. } => *id,
+            UpdateCharacterCommand::RollDeathSave { id, .. } => *id,
+            UpdateCharacterCommand::Stabilize { id } => *id,
+            UpdateCharacterCommand::Revive { id, .. } => *id,
+            UpdateCharacterCommand::UpdateResistances { id, .. } => *id,
+            UpdateCharacterCommand::UpdateVulnerabilities { id, .. } => *id,
+            UpdateCharacterCommand::UpdateImmunities { id, .. } => *id,
+            UpdateCharacterCommand::UpdateConcentration { id, .. } => *id,
+            UpdateCharacterCommand::UpdateArmorClass { id, .. } => *id,
+            UpdateCharacterCommand::UpdateAbilityScore { id, .. } => *id,
+            UpdateCharacterCommand::UpdateProficiencyBonus { id, .. } => *id,
+            UpdateCharacterCommand::UpdateSaveProficiencies { id, .. } => *id,
         }
     }
 }
@@ -37,23 +88,66 @@ impl UpdateCharacterCommand {
 #[serde(rename_all = "camelCase")]
 pub enum CharacterCommandResponse {
     UpdatedCharacter {
-        character: Character,
+        character: Box<Character>,
         messages: CharacterChangeMessages,
+        transitions: Vec<LifeStateChange>,
+        damage: Vec<DamageBreakdown>,
+        concentration_checks: Vec<ConcentrationCheck>,
+    },
+    UpdatedGroup {
+        group: CombatantGroup,
+        characters: Vec<Character>,
+        transitions: Vec<LifeStateChange>,
+        damage: Vec<DamageBreakdown>,
+        concentration_checks: Vec<ConcentrationCheck>,
     },
 }
 
 impl CharacterCommandResponse {
     pub fn updated(character: &Character) -> Self {
         Self::UpdatedCharacter {
-            character: character.clone(),
+            character: Box::new(character.clone()),
             messages: CharacterChangeMessages::none(),
+            transitions: Vec::new(),
+            damage: Vec::new(),
+            concentration_checks: Vec::new(),
         }
     }
 
     pub fn updated_with_messages(character: &Character, messages: CharacterChangeMessages) -> Self {
         Self::UpdatedCharacter {
-            character: character.clone(),
+            character: Box::new(character.clone()),
             messages,
+            transitions: Vec::new(),
+            damage: Vec::new(),
+            concentration_checks: Vec::new(),
+        }
+    }
+
+    pub fn with_transitions(mut self, changes: Vec<LifeStateChange>) -> Self {
+        match &mut self {
+            Self::UpdatedCharacter { transitions, .. } | Self::UpdatedGroup { transitions, .. } => *transitions = changes,
+        }
+        self
+    }
+
+    pub fn with_damage(mut self, breakdowns: Vec<DamageBreakdown>) -> Self {
+        match &mut self {
+            Self::UpdatedCharacter { damage, .. } | Self::UpdatedGroup { damage, .. } => *damage = breakdowns,
+        }
+        self
+    }
+
+    pub fn with_concentration_checks(mut self, checks: Vec<ConcentrationCheck>) -> Self {
+        match &mut self {
+            Self::UpdatedCharacter { concentration_checks, .. } | Self::UpdatedGroup { concentration_checks, .. } => *concentration_checks = checks,
+        }
+        self
+    }
+
+    pub fn damage(&self) -> &[DamageBreakdown] {
+        match self {
+            Self::UpdatedCharacter { damage, .. } | Self::UpdatedGroup { damage, .. } => damage,
         }
     }
 }
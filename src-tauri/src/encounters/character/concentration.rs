@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcentrationCheck {
+    pub id: Uuid,
+    pub character: Uuid,
+    pub name: String,
+    pub spell: String,
+    pub dc: i32,
+    pub modifier: i32,
+}
+
+impl ConcentrationCheck {
+    pub fn new<T: Into<String>, S: Into<String>>(character: Uuid, name: T, spell: S, modifier: i32, damage: i32) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            character,
+            name: name.into(),
+            spell: spell.into(),
+            dc: Self::dc(damage),
+            modifier,
+        }
+    }
+
+    pub fn dc(damage: i32) -> i32 {
+        (damage / 2).max(10)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dc() {
+        assert_eq!(ConcentrationCheck::dc(1), 10);
+        assert_eq!(ConcentrationCheck::dc(21), 10);
+        assert_eq!(ConcentrationCheck::dc(22), 11);
+        assert_eq!(ConcentrationCheck::new(Uuid::new_v4(), "Gandalf", "Hold Person", 2, 45).dc, 22);
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ConditionKind {
+    Blinded,
+    Charmed,
+    Deafened,
+    Frightened,
+    Grappled,
+    Incapacitated,
+    Invisible,
+    Paralyzed,
+    Petrified,
+    Poisoned,
+    Prone,
+    Restrained,
+    Stunned,
+    Unconscious,
+    Other(String),
+}
+
+impl ConditionKind {
+    pub fn name(&self) -> &str {
+        match self {
+            ConditionKind::Blinded => "blinded",
+            ConditionKind::Charmed => "charmed",
+            ConditionKind::Deafened => "deafened",
+            ConditionKind::Frightened => "frightened",
+            ConditionKind::Grappled => "grappled",
+            ConditionKind::Incapacitated => "incapacitated",
+            ConditionKind::Invisible => "invisible",
+            ConditionKind::Paralyzed => "paralyzed",
+            ConditionKind::Petrified => "petrified",
+            ConditionKind::Poisoned => "poisoned",
+            ConditionKind::Prone => "prone",
+            ConditionKind::Restrained => "restrained",
+            ConditionKind::Stunned => "stunned",
+            ConditionKind::Unconscious => "unconscious",
+            ConditionKind::Other(name) => name,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Eq, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ConditionDuration {
+    #[default]
+    UntilRemoved,
+    Rounds { rounds: u32 },
+    // Only counts once `character`'s next turn has `started`
+    EndOfTurn {
+        character: Uuid,
+        #[serde(default)]
+        started: bool,
+    },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Condition {
+    pub id: Uuid,
+    pub kind: ConditionKind,
+    #[serde(default)]
+    pub duration: ConditionDuration,
+    #[serde(default)]
+    pub source: Option<Uuid>,
+    #[serde(default)]
+    pub concentration: bool,
+}
+
+impl Condition {
+    pub fn new(kind: ConditionKind, duration: ConditionDuration, source: Option<Uuid>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            duration,
+            source,
+            concentration: false,
+        }
+    }
+
+    pub fn start_turn(&mut self, owners: &[Uuid]) {
+        if let ConditionDuration::EndOfTurn { character, started } = &mut self.duration {
+            if owners.contains(character) {
+                *started = true;
+            }
+        }
+    }
+
+    // False when a referenced character has no copy
+    pub fn remap(&mut self, ids: &HashMap<Uuid, Uuid>) -> bool {
+        if let Some(source) = &mut self.source {
+            match ids.get(source) {
+                Some(copy) => *source = *copy,
+                None => return false,
+            }
+        }
+        if let ConditionDuration::EndOfTurn { character, .. } = &mut self.duration {
+            match ids.get(character) {
+                Some(copy) => *character = *copy,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    // Returns whether the condition ran out
+    pub fn end_turn(&mut self, bearer: Uuid, owners: &[Uuid]) -> bool {
+        match &mut self.duration {
+            ConditionDuration::UntilRemoved => false,
+            ConditionDuration::Rounds { rounds } if owners.contains(&bearer) => {
+                *rounds = rounds.saturating_sub(1);
+                *rounds == 0
+            }
+            ConditionDuration::Rounds { .. } => false,
+            ConditionDuration::EndOfTurn { character, started } => *started && owners.contains(character),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpiredCondition {
+    pub id: Uuid,
+    pub name: String,
+    pub condition: Condition,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ConditionChange {
+    pub bearer: Uuid,
+    pub condition: Condition,
+    pub expired: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rounds_count_down_on_the_bearers_turns() {
+        let bearer = Uuid::new_v4();
+        let other = Uuid::new_v4();
+        let mut condition = Condition::new(ConditionKind::Poisoned, ConditionDuration::Rounds { rounds: 2 }, None);
+
+        assert!(!condition.end_turn(bearer, &[other]));
+        assert!(!condition.end_turn(bearer, &[bearer]));
+        assert_eq!(condition.duration, ConditionDuration::Rounds { rounds: 1 });
+        assert!(condition.end_turn(bearer, &[bearer]));
+    }
+
+    #[test]
+    fn test_end_of_next_turn() {
+        let bearer = Uuid::new_v4();
+        let wizard = Uuid::new_v4();
+        let duration = ConditionDuration::EndOfTurn { character: wizard, started: false };
+        let mut condition = Condition::new(ConditionKind::Frightened, duration, Some(wizard));
+
+        // Applied during the wizard's own turn, so the end of that turn does not count
+        assert!(!condition.end_turn(bearer, &[wizard]));
+        condition.start_turn(&[bearer]);
+        assert!(!condition.end_turn(bearer, &[bearer]));
+        condition.start_turn(&[wizard]);
+        assert!(condition.end_turn(bearer, &[wizard]));
+    }
+
+    #[test]
+    fn test_remap() {
+        let wizard = Uuid::new_v4();
+        let copy = Uuid::new_v4();
+        let ids = HashMap::from([(wizard, copy)]);
+        let duration = ConditionDuration::EndOfTurn { character: wizard, started: false };
+        let mut condition = Condition::new(ConditionKind::Frightened, duration, Some(wizard));
+
+        assert!(condition.remap(&ids));
+        assert_eq!(condition.source, Some(copy));
+        assert_eq!(condition.duration, ConditionDuration::EndOfTurn { character: copy, started: false });
+        assert!(!Condition::new(ConditionKind::Prone, ConditionDuration::default(), Some(Uuid::new_v4())).remap(&ids));
+    }
+
+    #[test]
+    fn test_until_removed() {
+        let bearer = Uuid::new_v4();
+        let mut condition = Condition::new(ConditionKind::Other(String::from("Hexed")), ConditionDuration::default(), None);
+        assert!(!condition.end_turn(bearer, &[bearer]));
+        assert_eq!(condition.kind.name(), "Hexed");
+    }
+}
@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, Ord, PartialOrd, Hash, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum DamageType {
+    Acid,
+    Bludgeoning,
+    Cold,
+    Fire,
+    Force,
+    Lightning,
+    Necrotic,
+    Piercing,
+    Poison,
+    Psychic,
+    Radiant,
+    Slashing,
+    Thunder,
+}
+
+impl DamageType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DamageType::Acid => "acid",
+            DamageType::Bludgeoning => "bludgeoning",
+            DamageType::Cold => "cold",
+            DamageType::Fire => "fire",
+            DamageType::Force => "force",
+            DamageType::Lightning => "lightning",
+            DamageType::Necrotic => "necrotic",
+            DamageType::Piercing => "piercing",
+            DamageType::Poison => "poison",
+            DamageType::Psychic => "psychic",
+            DamageType::Radiant => "radiant",
+            DamageType::Slashing => "slashing",
+            DamageType::Thunder => "thunder",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DamageBreakdown {
+    pub id: Uuid,
+    pub damage_type: Option<DamageType>,
+    pub rolled: i32,
+    pub immune: bool,
+    pub resisted: bool,
+    pub vulnerable: bool,
+    pub adjusted: i32,
+    pub absorbed: i32,
+    pub taken: i32,
+}
+
+impl DamageBreakdown {
+    // Resistance halves, rounding down, before vulnerability doubles
+    pub fn new(id: Uuid, rolled: i32, damage_type: Option<DamageType>, immune: bool, resisted: bool, vulnerable: bool) -> Self {
+        let adjusted = if immune {
+            0
+        } else {
+            let halved = if resisted { rolled / 2 } else { rolled };
+            if vulnerable { halved * 2 } else { halved }
+        };
+        Self {
+            id,
+            damage_type,
+            rolled,
+            immune,
+            resisted,
+            vulnerable,
+            adjusted,
+            absorbed: 0,
+            taken: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multipliers() {
+        let id = Uuid::new_v4();
+        let fire = Some(DamageType::Fire);
+        assert_eq!(DamageBreakdown::new(id, 7, fire, false, false, false).adjusted, 7);
+        assert_eq!(DamageBreakdown::new(id, 7, fire, false, true, false).adjusted, 3);
+        assert_eq!(DamageBreakdown::new(id, 7, fire, false, false, true).adjusted, 14);
+        assert_eq!(DamageBreakdown::new(id, 7, fire, false, true, true).adjusted, 6);
+        assert_eq!(DamageBreakdown::new(id, 7, fire, true, true, true).adjusted, 0);
+    }
+}
@@ -1,5 +1,13 @@
+mod abilities;
 mod character;
 mod commands;
+mod concentration;
+mod condition;
+mod damage;
 
+pub use abilities::*;
 pub use character::*;
-pub use commands::*;
\ No newline at end of file
+pub use commands::*;
+pub use concentration::*;
+pub use condition::*;
+pub use damage::*;
\ No newline at end of file
@@ -0,0 +1,335 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use uuid::Uuid;
+
+use crate::encounters::Encounter;
+use crate::encounters::character::{Character, DamageType, DeathSaves, HitPoints, LifeState, UpdateCharacterCommand};
+use crate::encounters::commands::EncounterStageCmd;
+use crate::encounters::initiative::SkipReason;
+use crate::services::unix_timestamp;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CombatEvent {
+    CharacterAdded { id: Uuid, name: String },
+    CharacterRemoved { id: Uuid, name: String },
+    CharacterDuplicated { id: Uuid, name: String, from: String },
+    CharacterMoved { id: Uuid, name: String, position: u32 },
+    GroupFormed { id: Uuid, name: String },
+    GroupDisbanded { id: Uuid, name: String },
+    InitiativeEntryAdded { id: Uuid, name: String, initiative: i32 },
+    InitiativeEntryRemoved { id: Uuid, name: String },
+    Renamed { id: Uuid, from: String, to: String },
+    InitiativeChanged { id: Uuid, name: String, from: i32, to: i32 },
+    InitiativeModifierChanged { id: Uuid, name: String, from: i32, to: i32 },
+    HitPointsChanged { id: Uuid, name: String, from: HitPoints, to: HitPoints },
+    Damaged {
+        id: Uuid,
+        name: String,
+        amount: i32,
+        absorbed: i32,
+        #[serde(default)]
+        damage_type: Option<DamageType>,
+    },
+    Healed { id: Uuid, name: String, amount: i32 },
+    PlayerChanged { id: Uuid, name: String, player: bool },
+    InitiativeRolled { id: Uuid, name: String, total: i32 },
+    Started { name: Option<String> },
+    SurpriseStarted { name: Option<String> },
+    Paused,
+    Resumed { name: Option<String> },
+    Stopped,
+    TurnPassed { from: Option<String>, to: Option<String> },
+    TurnReturned { from: Option<String>, to: Option<String> },
+    Delayed { name: Option<String>, to: Option<String> },
+    Rejoined { name: Option<String>, initiative: i32 },
+    Readied { name: Option<String>, trigger: String, to: Option<String> },
+    ReadyTriggered { name: Option<String> },
+    RoundStarted { round: u32 },
+    TurnSkipped { id: Uuid, name: String, reason: SkipReason },
+    ConditionAdded { id: Uuid, name: String, condition: String },
+    ConditionRemoved { id: Uuid, name: String, condition: String },
+    ConditionExpired { id: Uuid, name: String, condition: String },
+    DeathSaveRolled { id: Uuid, name: String, roll: i32, saves: DeathSaves },
+    LifeStateChanged { id: Uuid, name: String, state: LifeState },
+    ConcentrationStarted { id: Uuid, name: String, spell: String },
+    ConcentrationEnded { id: Uuid, name: String, spell: String },
+    ConcentrationChecked { id: Uuid, name: String, spell: String, dc: i32, passed: bool },
+    Undone,
+    Redone,
+    CheckpointRestored { label: String },
+}
+
+impl CombatEvent {
+    pub fn from_character_change(cmd: &UpdateCharacterCommand, before: &Character, after: &Character) -> Option<Self> {
+        if before == after {
+            return None;
+        }
+        let id = after.uuid();
+        let name = after.name.clone();
+        let event = match cmd {
+            UpdateCharacterCommand::UpdateName { .. } => Self::Renamed { id, from: before.name.clone(), to: name },
+            UpdateCharacterCommand::UpdateInitiative { .. } => Self::InitiativeChanged {
+                id,
+                name,
+                from: before.initiative,
+                to: after.initiative,
+            },
+            UpdateCharacterCommand::UpdateInitiativeModifier { .. } => Self::InitiativeModifierChanged {
+                id,
+                name,
+                from: before.initiative_modifier,
+                to: after.initiative_modifier,
+            },
+            UpdateCharacterCommand::UpdateCurrentHp { .. }
+            | UpdateCharacterCommand::UpdateTotalHp { .. }
+            | UpdateCharacterCommand::UpdateTemporaryHp { .. } => Self::HitPointsChanged { id, name, from: before.hp, to: after.hp },
+            UpdateCharacterCommand::Heal { .. } => Self::Healed { id, name, amount: after.hp.current - before.hp.current },
+            UpdateCharacterCommand::UpdatePlayer { player, .. } => Self::PlayerChanged { id, name, player: *player },
+            UpdateCharacterCommand::RollDeathSave { roll, .. } => Self::DeathSaveRolled { id, name, roll: *roll, saves: after.death_saves },
+            // Shows up as the change of life state it causes
+            UpdateCharacterCommand::Stabilize { .. } | UpdateCharacterCommand::Revive { .. } => return None,
+            UpdateCharacterCommand::AddCondition { kind, .. } => Self::ConditionAdded { id, name, condition: kind.name().to_string() },
+            UpdateCharacterCommand::RemoveCondition { condition, .. } => Self::ConditionRemoved {
+                id,
+                name,
+                condition: before.conditions.iter().find(|c| c.id == *condition)?.kind.name().to_string(),
+            },
+            // Turn order bookkeeping, which shows up in the log once it actually changes a turn
+            UpdateCharacterCommand::UpdateTieBreaker { .. }
+            | UpdateCharacterCommand::UpdateSurprised { .. }
+            | UpdateCharacterCommand::UpdateSkipTurn { .. } => return None,
+            // Stat block edits, which only matter once something targets the character
+            UpdateCharacterCommand::UpdateResistances { .. }
+            | UpdateCharacterCommand::UpdateVulnerabilities { .. }
+            | UpdateCharacterCommand::UpdateImmunities { .. }
+            | UpdateCharacterCommand::UpdateArmorClass { .. }
+            | UpdateCharacterCommand::UpdateAbilityScore { .. }
+            | UpdateCharacterCommand::UpdateProficiencyBonus { .. }
+            | UpdateCharacterCommand::UpdateSaveProficiencies { .. } => return None,
+            UpdateCharacterCommand::UpdateConcentration { spell: Some(spell), .. } => Self::ConcentrationStarted { id, name, spell: spell.clone() },
+            // Ending concentration also ends its conditions, which the encounter logs together
+            UpdateCharacterCommand::UpdateConcentration { spell: None, .. } => return None,
+            UpdateCharacterCommand::Damage { hp, damage_type, .. } => Self::Damaged {
+                id,
+                name,
+                amount: before.assess_damage(*hp, *damage_type).adjusted,
+                absorbed: before.hp.temporary - after.hp.temporary,
+                damage_type: *damage_type,
+            },
+        };
+        Some(event)
+    }
+
+    pub fn from_stage_command(cmd: &EncounterStageCmd, previous: Option<String>, encounter: &Encounter) -> Self {
+        let active = encounter.character_name(encounter.get_active_character_id());
+        match cmd {
+            EncounterStageCmd::Start => Self::Started { name: active },
+            EncounterStageCmd::StartWithSurprise => Self::SurpriseStarted { name: active },
+            EncounterStageCmd::Restart => Self::Resumed { name: active },
+            EncounterStageCmd::Pause => Self::Paused,
+            EncounterStageCmd::Stop => Self::Stopped,
+            EncounterStageCmd::Next => Self::TurnPassed { from: previous, to: active },
+            EncounterStageCmd::Previous => Self::TurnReturned { from: previous, to: active },
+            EncounterStageCmd::Delay => Self::Delayed { name: previous, to: active },
+            EncounterStageCmd::Rejoin { character, initiative } => Self::Rejoined {
+                name: encounter.character_name(Some(*character)),
+                initiative: *initiative,
+            },
+            EncounterStageCmd::Ready { trigger } => Self::Readied { name: previous, trigger: trigger.clone(), to: active },
+            EncounterStageCmd::TriggerReady { character } => Self::ReadyTriggered { name: encounter.character_name(Some(*character)) },
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::CharacterAdded { name, .. } => format!("{} joined the encounter", name),
+            Self::CharacterRemoved { name, .. } => format!("{} left the encounter", name),
+            Self::CharacterDuplicated { name, from, .. } => format!("{} joined the encounter as a copy of {}", name, from),
+            Self::CharacterMoved { name, position, .. } => format!("{} was moved to position {}", name, position),
+            Self::GroupFormed { name, .. } => format!("{} now act together", name),
+            Self::GroupDisbanded { name, .. } => format!("{} no longer act together", name),
+            Self::InitiativeEntryAdded { name, initiative, .. } => format!("{} takes an extra turn on initiative {}", name, initiative),
+            Self::InitiativeEntryRemoved { name, .. } => format!("{} no longer takes an extra turn", name),
+            Self::Renamed { from, to, .. } => format!("{} was renamed to {}", from, to),
+            Self::InitiativeChanged { name, from, to, .. } => format!("{}'s initiative changed from {} to {}", name, from, to),
+            Self::InitiativeModifierChanged { name, from, to, .. } => {
+                format!("{}'s initiative modifier changed from {} to {}", name, from, to)
+            }
+            Self::HitPointsChanged { name, from, to, .. } => {
+                format!("{}'s HP changed from {} to {}", name, describe_hp(from), describe_hp(to))
+            }
+            Self::Damaged { name, amount, absorbed, damage_type, .. } if *absorbed > 0 => {
+                format!("{} took {} ({} absorbed by temp HP)", name, describe_damage(*amount, damage_type), absorbed)
+            }
+            Self::Damaged { name, amount, damage_type, .. } => format!("{} took {}", name, describe_damage(*amount, damage_type)),
+            Self::Healed { name, amount, .. } => format!("{} healed {} HP", name, amount),
+            Self::PlayerChanged { name, player: true, .. } => format!("{} is now a player character", name),
+            Self::PlayerChanged { name, player: false, .. } => format!("{} is no longer a player character", name),
+            Self::InitiativeRolled { name, total, .. } => format!("{} rolled {} for initiative", name, total),
+            Self::Started { name: Some(name) } => format!("Encounter started with {}", name),
+            Self::Started { name: None } => String::from("Encounter started"),
+            Self::SurpriseStarted { name: Some(name) } => format!("Encounter started with a surprise round, {} acts first", name),
+            Self::SurpriseStarted { name: None } => String::from("Encounter started with a surprise round"),
+            Self::Paused => String::from("Encounter paused"),
+            Self::Resumed { name: Some(name) } => format!("Encounter resumed with {}", name),
+            Self::Resumed { name: None } => String::from("Encounter resumed"),
+            Self::Stopped => String::from("Encounter stopped"),
+            Self::TurnPassed { from: Some(from), to: Some(to) } => format!("Turn passed from {} to {}", from, to),
+            Self::TurnPassed { from: None, to: Some(to) } => format!("Turn passed to {}", to),
+            Self::TurnPassed { to: None, .. } => String::from("Turn ended"),
+            Self::TurnReturned { from: Some(from), to: Some(to) } => format!("Turn went back from {} to {}", from, to),
+            Self::TurnReturned { .. } => String::from("Turn went back"),
+            Self::Delayed { name, to } => match (name, to) {
+                (Some(name), Some(to)) => format!("{} delayed their turn, passing to {}", name, to),
+                (Some(name), None) => format!("{} delayed their turn", name),
+                _ => String::from("Turn was delayed"),
+            },
+            Self::Rejoined { name, initiative } => {
+                format!("{} rejoined the turn order at initiative {}", name.as_deref().unwrap_or("Someone"), initiative)
+            }
+            Self::Readied { name, trigger, .. } => {
+                format!("{} readied an action: {}", name.as_deref().unwrap_or("Someone"), trigger)
+            }
+            Self::ReadyTriggered { name } => format!("{} used their readied action", name.as_deref().unwrap_or("Someone")),
+            Self::RoundStarted { round } => format!("Round {} began", round),
+            Self::TurnSkipped { name, reason: SkipReason::Surprised, .. } => format!("{} was skipped while surprised", name),
+            Self::TurnSkipped { name, reason: SkipReason::Down, .. } => format!("{} was skipped while down", name),
+            Self::TurnSkipped { name, reason: SkipReason::SkipTurn, .. } => format!("{} skipped their turn", name),
+            Self::ConditionAdded { name, condition, .. } => format!("{} is now {}", name, condition),
+            Self::ConditionRemoved { name, condition, .. } => format!("{} is no longer {}", name, condition),
+            Self::DeathSaveRolled { name, roll, saves, .. } => format!(
+                "{} rolled {} on a death save ({} successes, {} failures)",
+                name, roll, saves.successes, saves.failures
+            ),
+            Self::LifeStateChanged { name, state: LifeState::Alive, .. } => format!("{} is back on their feet", name),
+            Self::LifeStateChanged { name, state: LifeState::Dying, .. } => format!("{} is dying", name),
+            Self::LifeStateChanged { name, state: LifeState::Stable, .. } => format!("{} is stable", name),
+            Self::LifeStateChanged { name, state: LifeState::Dead, .. } => format!("{} died", name),
+            Self::ConcentrationStarted { name, spell, .. } => format!("{} is concentrating on {}", name, spell),
+            Self::ConcentrationEnded { name, spell, .. } => format!("{} is no longer concentrating on {}", name, spell),
+            Self::ConcentrationChecked { name, spell, dc, passed: true, .. } => {
+                format!("{} kept concentrating on {} with a DC {} save", name, spell, dc)
+            }
+            Self::ConcentrationChecked { name, spell, dc, passed: false, .. } => {
+                format!("{} lost concentration on {} after failing a DC {} save", name, spell, dc)
+            }
+            Self::ConditionExpired { name, condition, .. } => format!("{} is no longer {} as the effect wore off", name, condition),
+            Self::Undone => String::from("Last change was undone"),
+            Self::Redone => String::from("Last undone change was redone"),
+            Self::CheckpointRestored { label } => format!("Restored checkpoint \"{}\"", label),
+        }
+    }
+}
+
+fn describe_hp(hp: &HitPoints) -> String {
+    match hp.temporary {
+        0 => format!("{}/{}", hp.current, hp.total),
+        temporary => format!("{}/{} (+{} temp)", hp.current, hp.total, temporary),
+    }
+}
+
+fn describe_damage(amount: i32, damage_type: &Option<DamageType>) -> String {
+    match damage_type {
+        Some(damage_type) => format!("{} {} damage", amount, damage_type.name()),
+        None => format!("{} damage", amount),
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CombatLogEntry {
+    pub timestamp: u32,
+    pub event: CombatEvent,
+}
+
+impl CombatLogEntry {
+    pub fn new(event: CombatEvent) -> Self {
+        Self {
+            timestamp: unix_timestamp(),
+            event,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CombatLogCommands {
+    List { id: Uuid },
+    ExportMarkdown { id: Uuid },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CombatLogResponse {
+    Entries(Vec<CombatLogEntry>),
+    Markdown(String),
+}
+
+pub fn to_markdown(encounter: &Encounter) -> String {
+    let mut markdown = format!("# {}\n\n", encounter.name());
+    for entry in encounter.log() {
+        markdown.push_str(&format!("- {}\n", entry.event.describe()));
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_damage_event() {
+        let mut before = Character::new("Frodo", 8, 18);
+        before.set_temporary_hp(3);
+        let mut after = before.clone();
+        after.damage(7, None);
+
+        let cmd = UpdateCharacterCommand::Damage { id: before.uuid(), hp: 7, damage_type: None, critical: false };
+        let event = CombatEvent::from_character_change(&cmd, &before, &after).unwrap();
+        assert_eq!(event.describe(), "Frodo took 7 damage (3 absorbed by temp HP)");
+
+        let cmd = UpdateCharacterCommand::Damage { id: before.uuid(), hp: 0, damage_type: None, critical: false };
+        assert_eq!(CombatEvent::from_character_change(&cmd, &before, &before), None);
+
+        // The log shows what got through the resistance rather than what was rolled
+        let mut before = Character::new("Smaug", 200, 10);
+        before.set_resistances(vec![DamageType::Fire]);
+        let mut after = before.clone();
+        after.damage(9, Some(DamageType::Fire));
+        let cmd = UpdateCharacterCommand::Damage { id: before.uuid(), hp: 9, damage_type: Some(DamageType::Fire), critical: false };
+        let event = CombatEvent::from_character_change(&cmd, &before, &after).unwrap();
+        assert_eq!(event.describe(), "Smaug took 4 fire damage");
+    }
+
+    #[test]
+    fn test_describe() {
+        let id = Uuid::new_v4();
+        let hp = HitPoints { current: 5, total: 8, temporary: 0 };
+        let healed = HitPoints { current: 8, total: 8, temporary: 2 };
+        let name = String::from("Frodo");
+
+        assert_eq!(CombatEvent::CharacterAdded { id, name: name.clone() }.describe(), "Frodo joined the encounter");
+        assert_eq!(CombatEvent::Healed { id, name: name.clone(), amount: 3 }.describe(), "Frodo healed 3 HP");
+        assert_eq!(CombatEvent::HitPointsChanged { id, name: name.clone(), from: hp, to: healed }.describe(), "Frodo's HP changed from 5/8 to 8/8 (+2 temp)");
+        assert_eq!(CombatEvent::Damaged { id, name: name.clone(), amount: 4, absorbed: 0, damage_type: None }.describe(), "Frodo took 4 damage");
+        assert_eq!(CombatEvent::Damaged { id, name, amount: 3, absorbed: 0, damage_type: Some(DamageType::Fire) }.describe(), "Frodo took 3 fire damage");
+
+        let event = CombatEvent::TurnPassed { from: Some(String::from("Sam")), to: Some(String::from("Pippin")) };
+        assert_eq!(event.describe(), "Turn passed from Sam to Pippin");
+        let event = CombatEvent::Started { name: Some(String::from("Sam")) };
+        assert_eq!(event.describe(), "Encounter started with Sam");
+        let event = CombatEvent::Readied { name: Some(String::from("Sam")), trigger: String::from("When the troll moves"), to: None };
+        assert_eq!(event.describe(), "Sam readied an action: When the troll moves");
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let mut encounter = Encounter::new("Lord of the Rings");
+        let frodo = Character::new("Frodo", 8, 18);
+        encounter.add_character(frodo.clone());
+        encounter.update_character(UpdateCharacterCommand::Damage { id: frodo.uuid(), hp: 3, damage_type: None, critical: false }).unwrap();
+
+        assert_eq!(to_markdown(&encounter), "# Lord of the Rings\n\n- Frodo joined the encounter\n- Frodo took 3 damage\n");
+    }
+}
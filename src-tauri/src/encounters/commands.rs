@@ -1,19 +1,30 @@
 use std::collections::HashMap;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use specta::Type;
 use uuid::Uuid;
 
+use crate::dice::DiceRoll;
 use crate::encounters::{Character, Encounter, EncounterCollection};
 use crate::encounters::character::CharacterChangeMessages;
+use crate::encounters::combat_log::CombatEvent;
+use crate::encounters::encounter::EncounterChangeMessages;
+use crate::encounters::initiative::TieBreakPolicy;
 
 #[derive(Clone, Debug, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub enum EncounterStageCmd {
     Start,
+    StartWithSurprise,
     Restart,
     Pause,
     Stop,
     Next,
+    Previous,
+    Delay,
+    Rejoin { character: Uuid, initiative: i32 },
+    Ready { trigger: String },
+    TriggerReady { character: Uuid },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Type)]
@@ -27,7 +38,7 @@ pub struct UpdateStageCommand {
 #[serde(rename_all = "camelCase")]
 pub struct AddCharacterCommand {
     pub id: Uuid,
-    pub character: Character,
+    pub character: Box<Character>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Type)]
@@ -37,12 +48,79 @@ pub struct AddCharacterResult {
     character_change: CharacterChangeMessages,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EncounterListResult {
+    encounters: HashMap<Uuid, Encounter>,
+    encounter_change: EncounterChangeMessages,
+}
+
+// Without a character the turn belongs to the encounter, like lair actions
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AddInitiativeEntryCommand {
+    pub id: Uuid,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub character: Option<Uuid>,
+    pub initiative: i32,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RollInitiativeOptions {
+    #[serde(default)]
+    pub only_missing: bool,
+    #[serde(default)]
+    pub skip_players: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RollInitiativeCommand {
+    pub id: Uuid,
+    #[serde(default)]
+    pub options: RollInitiativeOptions,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InitiativeRoll {
+    pub id: Uuid,
+    pub name: String,
+    pub roll: DiceRoll,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InitiativeRollResult {
+    encounter: Encounter,
+    rolls: Vec<InitiativeRoll>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub enum EncounterCommands {
     ListEncounter,
     UpdateStage(UpdateStageCommand),
     AddCharacter(AddCharacterCommand),
+    Undo { id: Uuid },
+    Redo { id: Uuid },
+    RollInitiative(RollInitiativeCommand),
+    UpdateTieBreak { id: Uuid, policy: TieBreakPolicy },
+    RemoveCharacter { id: Uuid, character: Uuid },
+    DuplicateCharacter { id: Uuid, character: Uuid },
+    MoveCharacter { id: Uuid, character: Uuid, index: u32 },
+    CreateEncounter { name: String },
+    RenameEncounter { id: Uuid, name: String },
+    CloneEncounter { id: Uuid },
+    DeleteEncounter { id: Uuid },
+    GroupCharacters { id: Uuid, name: String, characters: Vec<Uuid> },
+    UngroupCharacters { id: Uuid, group: Uuid },
+    AddInitiativeEntry(AddInitiativeEntryCommand),
+    RemoveInitiativeEntry { id: Uuid, entry: Uuid },
+    ResolveConcentrationCheck { id: Uuid, check: Uuid, passed: bool },
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Type)]
@@ -51,6 +129,8 @@ pub enum EncounterCommandResponse {
     EncounterList(HashMap<Uuid, Encounter>),
     EncounterChanged(Encounter),
     CharacterAdded(AddCharacterResult),
+    InitiativeRolled(InitiativeRollResult),
+    EncounterListChanged(EncounterListResult),
 }
 
 impl EncounterCommandResponse{
@@ -58,18 +138,55 @@ impl EncounterCommandResponse{
         Ok(Self::EncounterList(collection.list_encounters()))
     }
 
+    pub fn list_changed(collection: &EncounterCollection, messages: &EncounterChangeMessages) -> Self {
+        Self::EncounterListChanged(EncounterListResult {
+            encounters: collection.list_encounters(),
+            encounter_change: messages.clone(),
+        })
+    }
+
     pub fn from_stage_command(encounter: &mut Encounter, cmd: EncounterStageCmd) -> Result<Self, String> {
-        match cmd {
-            EncounterStageCmd::Start => encounter.start()?,
-            EncounterStageCmd::Restart => encounter.restart()?,
-            EncounterStageCmd::Pause => encounter.pause()?,
-            EncounterStageCmd::Stop => encounter.stop()?,
-            EncounterStageCmd::Next => encounter.next()?,
+        let previous = encounter.character_name(encounter.get_active_character_id());
+        let round = encounter.round();
+        encounter.update_stage(cmd.clone())?;
+
+        let event = CombatEvent::from_stage_command(&cmd, previous, encounter);
+        encounter.record_event(event);
+        for expired in encounter.expired_conditions().to_vec() {
+            encounter.record_event(CombatEvent::ConditionExpired { id: expired.id, name: expired.name, condition: expired.condition.kind.name().to_string() });
+        }
+        for skipped in encounter.skipped_turns().to_vec() {
+            encounter.record_event(CombatEvent::TurnSkipped { id: skipped.id, name: skipped.name, reason: skipped.reason });
+        }
+        if round > 0 && encounter.round() > round {
+            encounter.record_event(CombatEvent::RoundStarted { round: encounter.round() });
         }
+        Ok(Self::EncounterChanged(encounter.clone()))
+    }
+
+    pub fn from_tie_break(encounter: &mut Encounter, policy: TieBreakPolicy) -> Self {
+        encounter.set_tie_break(policy);
+        Self::EncounterChanged(encounter.clone())
+    }
+
+    pub fn from_undo(encounter: &mut Encounter) -> Result<Self, String> {
+        encounter.undo()?;
+        Ok(Self::EncounterChanged(encounter.clone()))
+    }
 
+    pub fn from_redo(encounter: &mut Encounter) -> Result<Self, String> {
+        encounter.redo()?;
         Ok(Self::EncounterChanged(encounter.clone()))
     }
 
+    pub fn from_roll_initiative<R: Rng>(encounter: &mut Encounter, options: &RollInitiativeOptions, rng: &mut R) -> Result<Self, String> {
+        let rolls = encounter.roll_initiative(options, rng)?;
+        Ok(Self::InitiativeRolled(InitiativeRollResult {
+            encounter: encounter.clone(),
+            rolls,
+        }))
+    }
+
     pub fn character_added(encounter: &Encounter, message: &CharacterChangeMessages) -> Self {
         Self::CharacterAdded(AddCharacterResult {
             encounter: encounter.clone(),
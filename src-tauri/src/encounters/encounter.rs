@@ -1,14 +1,22 @@
+use std::cmp::{Ordering, Reverse};
 use std::collections::HashMap;
 use std::sync::{Arc};
 
 use tokio::sync::{Mutex, MutexGuard};
 use tauri::State;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use specta::Type;
 
+use crate::dice;
 use crate::encounters::Character;
-use crate::encounters::character::{CharacterChangeMessages, UpdateCharacterCommand, CharacterCommandResponse};
+use crate::encounters::character::{Ability, CharacterChangeMessages, ConcentrationCheck, Condition, DamageBreakdown, ConditionChange, ExpiredCondition, LifeStateChange, UpdateCharacterCommand, CharacterCommandResponse};
+use crate::encounters::combat_log::{CombatEvent, CombatLogEntry};
+use crate::encounters::commands::{EncounterStageCmd, InitiativeRoll, RollInitiativeOptions};
+use crate::encounters::history::{EncounterChange, EncounterHistory, RosterState, TurnState, HISTORY_LIMIT};
+use crate::encounters::initiative::{CombatantGroup, InitiativeEntry, PassedTurn, ReadiedAction, SkipReason, SkippedTurn, TieBreakPolicy};
+use crate::services::FrontendMessage;
 
 pub type EncounterManagerState<'a> = State<'a, EncounterManager>;
 
@@ -48,13 +56,92 @@ impl EncounterCollection {
         self.encounters.clone()
     }
 
+    pub fn remove_encounter(&mut self, id: Uuid) -> Option<Encounter> {
+        self.encounters.remove(&id)
+    }
+
+    pub fn find_encounter(&self, id: Uuid) -> Option<&Encounter> {
+        self.encounters.get(&id)
+    }
+
     pub fn find_encounter_mut(&mut self, id: Uuid) -> Option<&mut Encounter> {
         self.encounters.get_mut(&id)
     }
+
+    pub fn validate_name(&self, name: &str, id: Option<Uuid>) -> EncounterChangeMessages {
+        let mut messages = EncounterChangeMessages::none();
+        if name.trim().is_empty() {
+            messages.add_name_error_message("Name cannot be empty");
+        } else if self.encounters.values().any(|e| Some(e.id) != id && e.name == name) {
+            messages.add_name_error_message(format!("An encounter named {} already exists", name));
+        }
+        messages
+    }
+
+    pub fn create_encounter<T: Into<String>>(&mut self, name: T) -> &Encounter {
+        let encounter = Encounter::new(name);
+        let id = encounter.id;
+        self.add_encounter(encounter);
+        &self.encounters[&id]
+    }
+
+    pub fn rename_encounter<T: Into<String>>(&mut self, id: Uuid, name: T) -> Result<&Encounter, String> {
+        let encounter = self.find_encounter_mut(id).ok_or("Encounter not found")?;
+        encounter.name = name.into();
+        Ok(encounter)
+    }
+
+    pub fn clone_encounter(&mut self, id: Uuid) -> Result<&Encounter, String> {
+        let original = self.find_encounter(id).ok_or("Encounter not found")?;
+        let name = numbered_name(&original.name, |name| self.encounters.values().any(|e| e.name == name));
+        let copy = original.duplicate(name);
+        let copy_id = copy.id;
+        self.add_encounter(copy);
+        Ok(&self.encounters[&copy_id])
+    }
+
+    pub fn delete_encounter(&mut self, id: Uuid) -> Result<Encounter, String> {
+        self.remove_encounter(id).ok_or("Encounter not found".to_string())
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EncounterChangeMessages {
+    pub name: Vec<FrontendMessage>,
+}
+
+impl EncounterChangeMessages {
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    pub fn add_name_error_message<T: Into<String>>(&mut self, message: T) {
+        let message = FrontendMessage::error(message);
+        if !(self.name.contains(&message)) {
+            self.name.push(message);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.name.is_empty()
+    }
+}
+
+// Counts up from the number `name` already ends with, so "Room 101" is followed by "Room 102"
+fn numbered_name<F: Fn(&str) -> bool>(name: &str, taken: F) -> String {
+    let (base, first) = match name.rsplit_once(' ').map(|(base, number)| (base, number.parse::<u32>())) {
+        Some((base, Ok(number))) => (base, number.saturating_add(1)),
+        _ => (name, 2),
+    };
+    (first..)
+        .map(|number| format!("{} {}", base, number))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_default()
 }
 
 
-#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct Encounter {
     id: Uuid,
@@ -62,6 +149,25 @@ pub struct Encounter {
     characters: Vec<Character>,
     active_character: Option<Uuid>,
     last_active_character: Option<Uuid>,
+    round: u32,
+    turn: u32,
+    acted: Vec<Uuid>,
+    delayed: Vec<Uuid>,
+    readied: Vec<ReadiedAction>,
+    surprise: bool,
+    // Lets `previous` bring the surprise back when stepping into round 1
+    surprise_round: bool,
+    skipped: Vec<SkippedTurn>,
+    expired: Vec<ExpiredCondition>,
+    passed_turns: Vec<PassedTurn>,
+    tie_break: TieBreakPolicy,
+    manual_order: bool,
+    groups: Vec<CombatantGroup>,
+    initiative_entries: Vec<InitiativeEntry>,
+    concentration_checks: Vec<ConcentrationCheck>,
+    log: Vec<CombatLogEntry>,
+    #[serde(skip)]
+    history: EncounterHistory,
 }
 
 impl Encounter {
@@ -72,23 +178,123 @@ impl Encounter {
             characters: Vec::new(),
             active_character: None,
             last_active_character: None,
+            round: 0,
+            turn: 0,
+            acted: Vec::new(),
+            delayed: Vec::new(),
+            readied: Vec::new(),
+            surprise: false,
+            surprise_round: false,
+            skipped: Vec::new(),
+            expired: Vec::new(),
+            passed_turns: Vec::new(),
+            tie_break: TieBreakPolicy::default(),
+            manual_order: false,
+            groups: Vec::new(),
+            initiative_entries: Vec::new(),
+            concentration_checks: Vec::new(),
+            log: Vec::new(),
+            history: EncounterHistory::default(),
         }
     }
 
+    pub fn duplicate<T: Into<String>>(&self, name: T) -> Encounter {
+        let mut encounter = Encounter::new(name);
+        let mut ids = HashMap::new();
+        encounter.characters = self.characters
+            .iter()
+            .map(|c| {
+                let copy = c.duplicate(c.name.clone());
+                ids.insert(c.uuid(), copy.uuid());
+                copy
+            })
+            .collect();
+        encounter.characters.iter_mut().for_each(|c| c.remap_conditions(&ids));
+        // References to characters that no longer exist are dropped rather than copied along
+        encounter.groups = self.groups
+            .iter()
+            .map(|g| CombatantGroup::new(g.name.clone(), g.members.iter().filter_map(|id| ids.get(id).copied()).collect()))
+            .filter(|g| g.members.len() > 1)
+            .collect();
+        encounter.initiative_entries = self.initiative_entries
+            .iter()
+            .filter_map(|e| match e.character {
+                Some(id) => ids.get(&id).map(|copy| InitiativeEntry { id: Uuid::new_v4(), character: Some(*copy), ..e.clone() }),
+                None => Some(InitiativeEntry { id: Uuid::new_v4(), ..e.clone() }),
+            })
+            .collect();
+        encounter.tie_break = self.tie_break;
+        encounter.manual_order = self.manual_order;
+        encounter
+    }
+
     pub fn id(&self) -> String {
         self.id.to_string()
     }
 
+    pub fn uuid(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn get_active_character_id(&self) -> Option<Uuid> {
         self.active_character
     }
 
+    pub fn round(&self) -> u32 {
+        self.round
+    }
+
+    pub fn set_tie_break(&mut self, policy: TieBreakPolicy) {
+        self.tie_break = policy;
+        self.manual_order = false;
+        self.sort_characters();
+    }
+
+    // A group's turn is named after the group
+    pub fn character_name(&self, id: Option<Uuid>) -> Option<String> {
+        let id = id?;
+        if let Some(entry) = self.initiative_entries.iter().find(|e| e.id == id) {
+            return Some(entry.name.clone());
+        }
+        if let Some(group) = self.groups.iter().find(|g| g.leader() == id) {
+            return Some(group.name.clone());
+        }
+        self.characters.iter().find(|c| c.uuid() == id).map(|c| c.name.clone())
+    }
+
+    pub fn log(&self) -> &[CombatLogEntry] {
+        &self.log
+    }
+
+    pub fn record_event(&mut self, event: CombatEvent) {
+        self.log.push(CombatLogEntry::new(event));
+    }
+
+    // Replacing an encounter's state never rewrites its log
+    pub fn continue_log_from(&mut self, other: &Encounter) {
+        self.log = other.log.clone();
+    }
+
     pub fn add_character(&mut self, new_character: Character) {
         if self.characters.iter().any(|c| c.is_same_as(&new_character)) {
             return;
         }
-        self.characters.push(new_character);
-        self.characters.sort();
+        self.record_event(CombatEvent::CharacterAdded { id: new_character.uuid(), name: new_character.name.clone() });
+        if self.manual_order {
+            // Slot the newcomer in after everyone who goes before it, without disturbing the order the user chose
+            let index = self.characters
+                .iter()
+                .rposition(|c| self.tie_break.compare(c, &new_character) == Ordering::Less)
+                .map_or(0, |index| index + 1);
+            self.characters.insert(index, new_character);
+        } else {
+            self.characters.push(new_character);
+        }
+        self.sort_characters();
     }
 
     pub fn find_character(&self, id: String) -> Option<&Character> {
@@ -103,12 +309,362 @@ impl Encounter {
         self.characters.clone()
     }
 
-    pub fn remove_character(&mut self, character: Character) {
-        self.characters.retain(|c| !c.is_same_as(&character));
+    pub fn roll_initiative<R: Rng>(&mut self, options: &RollInitiativeOptions, rng: &mut R) -> Result<Vec<InitiativeRoll>, String> {
+        let before = self.roster_state();
+        let mut rolls = Vec::new();
+        // A group rolls once, for the member holding its slot
+        let followers: Vec<Uuid> = self.groups.iter().flat_map(|g| g.members[1..].to_vec()).collect();
+        let tie_break = self.tie_break;
+        for character in self.characters.iter_mut() {
+            if followers.contains(&character.uuid())
+                || (options.only_missing && character.has_initiative())
+                || (options.skip_players && character.player) {
+                continue;
+            }
+            let roll = dice::roll(&format!("1d20{:+}", character.initiative_modifier), rng)?;
+            character.set_initiative(roll.total);
+            // A tie-breaker the DM already set is kept
+            if tie_break == TieBreakPolicy::TieBreakerRoll && character.tie_breaker.is_none() {
+                character.tie_breaker = Some(rng.gen_range(1..=20));
+            }
+            rolls.push(InitiativeRoll { id: character.uuid(), name: character.name.clone(), roll });
+        }
+
+        for roll in &rolls {
+            self.record_event(CombatEvent::InitiativeRolled { id: roll.id, name: roll.name.clone(), total: roll.roll.total });
+        }
+        for leader in self.groups.iter().map(CombatantGroup::leader).collect::<Vec<_>>() {
+            self.share_initiative(leader);
+        }
+        self.manual_order = false;
+        self.sort_characters();
+        self.record_change(before);
+        Ok(rolls)
+    }
+
+    pub fn remove_character(&mut self, id: Uuid) -> Result<Character, String> {
+        let index = self.position_of(id)?;
+        let before = self.roster_state();
+        let removed = self.characters.remove(index);
+
+        // The next member of a group takes over the slot, so the group keeps its place in the round
+        let successor = self.group_of(id).filter(|g| g.leader() == id).map(|g| g.members[1]);
+        self.groups.iter_mut().for_each(|g| g.members.retain(|member| *member != id));
+        self.groups.retain(|g| g.members.len() > 1);
+        if let Some(successor) = successor {
+            self.hand_over_slot(id, successor);
+        }
+
+        // Extra turns of a character leave with it
+        let entries: Vec<Uuid> = self.initiative_entries.iter().filter(|e| e.character == Some(id)).map(|e| e.id).collect();
+        self.initiative_entries.retain(|e| e.character != Some(id));
+        for slot in entries.into_iter().chain([id]) {
+            self.forget_slot(slot);
+        }
+
+        let ended = self.break_concentration(id);
+
+        self.record_change(before);
+        self.record_event(CombatEvent::CharacterRemoved { id, name: removed.name.clone() });
+        ended.into_iter().for_each(|event| self.record_event(event));
+        Ok(removed)
+    }
+
+    // Extra turns lose initiative ties, the way lair actions do
+    pub fn add_initiative_entry(&mut self, name: Option<String>, character: Option<Uuid>, initiative: i32) -> Result<InitiativeEntry, String> {
+        let owner = match character {
+            Some(id) => Some(self.position_of(id).map(|index| self.characters[index].name.clone())?),
+            None => None,
+        };
+        let name = name.or(owner).unwrap_or_else(|| String::from("Lair"));
+        if name.trim().is_empty() {
+            return Err(String::from("Name cannot be empty"));
+        }
+
+        let before = self.roster_state();
+        let entry = InitiativeEntry { id: Uuid::new_v4(), name, initiative, character };
+        self.initiative_entries.push(entry.clone());
+        self.record_change(before);
+        self.record_event(CombatEvent::InitiativeEntryAdded { id: entry.id, name: entry.name.clone(), initiative });
+        Ok(entry)
+    }
+
+    pub fn remove_initiative_entry(&mut self, id: Uuid) -> Result<InitiativeEntry, String> {
+        let index = self.initiative_entries.iter().position(|e| e.id == id).ok_or("Initiative entry not found")?;
+        let before = self.roster_state();
+        let entry = self.initiative_entries.remove(index);
+        self.forget_slot(id);
+
+        self.record_change(before);
+        self.record_event(CombatEvent::InitiativeEntryRemoved { id, name: entry.name.clone() });
+        Ok(entry)
+    }
+
+    fn forget_slot(&mut self, id: Uuid) {
+        self.acted.retain(|acted| *acted != id);
+        self.delayed.retain(|delayed| *delayed != id);
+        self.readied.retain(|action| action.id != id);
+        self.skipped.retain(|skipped| skipped.id != id);
+        self.passed_turns.retain(|turn| turn.id != id);
+        if self.last_active_character == Some(id) {
+            self.last_active_character = None;
+        }
+        if self.active_character == Some(id) {
+            // With nobody left able to act the encounter simply has no active character
+            let next = self.next_actor().unwrap_or(None);
+            self.activate(next);
+        }
+        self.sync_turn();
+    }
+
+    pub fn duplicate_character(&mut self, id: Uuid) -> Result<Character, String> {
+        let index = self.position_of(id)?;
+        let before = self.roster_state();
+        let original = self.characters[index].name.clone();
+        let mut duplicate = self.characters[index].duplicate(numbered_name(&original, |name| self.characters.iter().any(|c| c.name == name)));
+        // Conditions the original caused on itself or timed to its own turn follow the copy
+        let mut ids: HashMap<Uuid, Uuid> = self.characters.iter().map(|c| (c.uuid(), c.uuid())).collect();
+        ids.insert(id, duplicate.uuid());
+        duplicate.remap_conditions(&ids);
+
+        // The copy shares its original's place in the round, so it does not get an extra turn
+        if self.acted.contains(&id) {
+            self.acted.push(duplicate.uuid());
+        }
+        if let Some(group) = self.groups.iter_mut().find(|g| g.contains(id)) {
+            let position = group.members.iter().position(|member| *member == id).unwrap_or_default();
+            group.members.insert(position + 1, duplicate.uuid());
+        }
+        self.characters.insert(index + 1, duplicate.clone());
+        self.sort_characters();
+
+        self.record_change(before);
+        self.record_event(CombatEvent::CharacterDuplicated { id: duplicate.uuid(), name: duplicate.name.clone(), from: original });
+        Ok(duplicate)
+    }
+
+    // Automatic sorting stays off until initiative is rolled or the tie-break changes
+    pub fn move_character(&mut self, id: Uuid, index: usize) -> Result<(), String> {
+        let from = self.position_of(id)?;
+        if index >= self.characters.len() {
+            return Err(format!("Cannot move a character to position {} of {}", index + 1, self.characters.len()));
+        }
+        let before = self.roster_state();
+        let character = self.characters.remove(from);
+        let name = character.name.clone();
+        self.characters.insert(index, character);
+        self.manual_order = true;
+        self.gather_groups();
+        self.sync_turn();
+
+        self.record_change(before);
+        self.record_event(CombatEvent::CharacterMoved { id, name, position: index as u32 + 1 });
+        Ok(())
     }
 
     pub fn update_character(&mut self, cmd: UpdateCharacterCommand) -> Result<CharacterCommandResponse, String> {
+        if let Some(group) = self.groups.iter().find(|g| g.id == cmd.id()).cloned() {
+            return self.update_group(group, cmd);
+        }
+        // Members share one initiative slot, so changing it for one changes it for all of them
+        let shared = matches!(cmd, UpdateCharacterCommand::UpdateInitiative { .. } | UpdateCharacterCommand::UpdateTieBreaker { .. });
+        if let Some(group) = self.group_of(cmd.id()).filter(|_| shared).cloned() {
+            return self.update_group(group, cmd);
+        }
+
+        // Concentration reaches other characters through its linked conditions, so it takes the whole roster to undo
+        let concentrating = self.characters.iter().any(|c| c.uuid() == cmd.id() && c.concentration.is_some());
+        let roster = concentrating.then(|| self.roster_state());
         let character = self.find_character_mut(cmd.id()).ok_or(format!("Character with id {} not found", cmd.id()))?;
+        let before = character.clone();
+        let response = Self::apply_character_command(character, cmd.clone())?;
+        let event = CombatEvent::from_character_change(&cmd, &before, character);
+        let transitions: Vec<LifeStateChange> = LifeStateChange::between(&before, character).into_iter().collect();
+        let mut events: Vec<CombatEvent> = event.into_iter().collect();
+        let checks = self.follow_concentration(&before, response.damage(), &mut events);
+
+        self.sort_characters();
+        self.record_change(roster.unwrap_or(EncounterChange::Character(before)));
+        events.into_iter().for_each(|event| self.record_event(event));
+        self.record_transitions(&transitions);
+        Ok(response.with_transitions(transitions).with_concentration_checks(checks))
+    }
+
+    fn update_group(&mut self, group: CombatantGroup, cmd: UpdateCharacterCommand) -> Result<CharacterCommandResponse, String> {
+        let allowed = matches!(
+            cmd,
+            UpdateCharacterCommand::Damage { .. }
+                | UpdateCharacterCommand::Heal { .. }
+                | UpdateCharacterCommand::UpdateInitiative { .. }
+                | UpdateCharacterCommand::UpdateTieBreaker { .. }
+        );
+        if !allowed {
+            return Err(String::from("Only damage, healing and initiative can be changed for a whole group"));
+        }
+
+        let before = self.roster_state();
+        let mut events = Vec::new();
+        let mut transitions = Vec::new();
+        let mut damage = Vec::new();
+        let mut members = Vec::new();
+        for id in &group.members {
+            let character = self.find_character_mut(*id).ok_or(format!("Character with id {} not found", id))?;
+            let previous = character.clone();
+            let response = Self::apply_character_command(character, cmd.clone())?;
+            damage.extend_from_slice(response.damage());
+            events.extend(CombatEvent::from_character_change(&cmd, &previous, character));
+            transitions.extend(LifeStateChange::between(&previous, character));
+            members.push(previous);
+        }
+        let mut checks = Vec::new();
+        for previous in &members {
+            checks.extend(self.follow_concentration(previous, &damage, &mut events));
+        }
+
+        self.sort_characters();
+        self.record_change(before);
+        events.into_iter().for_each(|event| self.record_event(event));
+        self.record_transitions(&transitions);
+        let characters = self.characters.iter().filter(|c| group.contains(c.uuid())).cloned().collect();
+        Ok(CharacterCommandResponse::UpdatedGroup { group, characters, transitions, damage, concentration_checks: checks })
+    }
+
+    fn follow_concentration(&mut self, before: &Character, damage: &[DamageBreakdown], events: &mut Vec<CombatEvent>) -> Vec<ConcentrationCheck> {
+        let Some(spell) = before.concentration.clone() else {
+            return Vec::new();
+        };
+        let id = before.uuid();
+        let Some(after) = self.characters.iter().find(|c| c.uuid() == id) else {
+            return Vec::new();
+        };
+        let name = after.name.clone();
+        let modifier = after.saving_throw(Ability::Constitution);
+        if after.concentration.as_ref() != Some(&spell) {
+            events.push(CombatEvent::ConcentrationEnded { id, name, spell });
+            events.extend(self.break_concentration(id));
+            return Vec::new();
+        }
+
+        let checks: Vec<ConcentrationCheck> = damage
+            .iter()
+            .filter(|d| d.id == id && d.adjusted > 0)
+            .map(|d| ConcentrationCheck::new(id, name.clone(), spell.clone(), modifier, d.adjusted))
+            .collect();
+        self.concentration_checks.extend(checks.iter().cloned());
+        checks
+    }
+
+    fn break_concentration(&mut self, caster: Uuid) -> Vec<CombatEvent> {
+        self.concentration_checks.retain(|check| check.character != caster);
+        let mut events = Vec::new();
+        for character in &mut self.characters {
+            let (linked, kept): (Vec<Condition>, Vec<Condition>) = std::mem::take(&mut character.conditions)
+                .into_iter()
+                .partition(|c| c.concentration && c.source == Some(caster));
+            character.conditions = kept;
+            events.extend(linked.iter().map(|c| CombatEvent::ConditionRemoved {
+                id: character.uuid(),
+                name: character.name.clone(),
+                condition: c.kind.name().to_string(),
+            }));
+        }
+        events
+    }
+
+    pub fn resolve_concentration_check(&mut self, id: Uuid, passed: bool) -> Result<ConcentrationCheck, String> {
+        let index = self.concentration_checks.iter().position(|c| c.id == id).ok_or("Concentration check not found")?;
+        let before = self.roster_state();
+        let check = self.concentration_checks.remove(index);
+        let mut events = vec![CombatEvent::ConcentrationChecked {
+            id: check.character,
+            name: check.name.clone(),
+            spell: check.spell.clone(),
+            dc: check.dc,
+            passed,
+        }];
+        if !passed {
+            if let Some(character) = self.characters.iter_mut().find(|c| c.uuid() == check.character) {
+                character.concentration = None;
+            }
+            events.extend(self.break_concentration(check.character));
+        }
+
+        self.record_change(before);
+        events.into_iter().for_each(|event| self.record_event(event));
+        Ok(check)
+    }
+
+    fn record_transitions(&mut self, transitions: &[LifeStateChange]) {
+        for change in transitions {
+            self.record_event(CombatEvent::LifeStateChanged { id: change.id, name: change.name.clone(), state: change.to });
+        }
+    }
+
+    // Everyone takes the initiative of the first member, which holds the slot
+    pub fn group_characters<T: Into<String>>(&mut self, name: T, members: &[Uuid]) -> Result<CombatantGroup, String> {
+        let name = name.into();
+        if name.trim().is_empty() {
+            return Err(String::from("Name cannot be empty"));
+        }
+        let mut members = members.to_vec();
+        members.sort_by_key(|id| self.characters.iter().position(|c| c.uuid() == *id));
+        members.dedup();
+        if members.len() < 2 {
+            return Err(String::from("A group needs at least two characters"));
+        }
+        for id in &members {
+            let index = self.position_of(*id)?;
+            if self.group_of(*id).is_some() {
+                return Err(format!("{} is already in a group", self.characters[index].name));
+            }
+        }
+
+        let before = self.roster_state();
+        let group = CombatantGroup::new(name, members);
+        // Whatever the other members were doing on their own is now covered by the group's slot
+        let followers = &group.members[1..];
+        if self.active_character.is_some_and(|id| followers.contains(&id)) {
+            self.activate(Some(group.leader()));
+        }
+        if self.last_active_character.is_some_and(|id| followers.contains(&id)) {
+            self.last_active_character = Some(group.leader());
+        }
+        self.acted.retain(|id| !followers.contains(id));
+        self.delayed.retain(|id| !followers.contains(id));
+        self.readied.retain(|action| !followers.contains(&action.id));
+        self.groups.push(group.clone());
+        self.share_initiative(group.leader());
+        self.sort_characters();
+
+        self.record_change(before);
+        self.record_event(CombatEvent::GroupFormed { id: group.id, name: group.name.clone() });
+        Ok(group)
+    }
+
+    pub fn ungroup_characters(&mut self, id: Uuid) -> Result<CombatantGroup, String> {
+        let index = self.groups.iter().position(|g| g.id == id).ok_or("Group not found")?;
+        let before = self.roster_state();
+        let group = self.groups.remove(index);
+
+        let leader = group.leader();
+        let acted = self.acted.contains(&leader) || self.active_character == Some(leader);
+        let delayed = self.delayed.contains(&leader);
+        for follower in &group.members[1..] {
+            if acted {
+                self.acted.push(*follower);
+            }
+            if delayed {
+                self.delayed.push(*follower);
+            }
+        }
+        self.sort_characters();
+
+        self.record_change(before);
+        self.record_event(CombatEvent::GroupDisbanded { id: group.id, name: group.name.clone() });
+        Ok(group)
+    }
+
+    fn apply_character_command(character: &mut Character, cmd: UpdateCharacterCommand) -> Result<CharacterCommandResponse, String> {
 
         match cmd {
             UpdateCharacterCommand::UpdateName { name, .. } => {
@@ -140,176 +696,1325 @@ impl Encounter {
                 character.heal(hp);
                 Ok(CharacterCommandResponse::updated(character))
             }
-            UpdateCharacterCommand::Damage { hp, .. } => {
-                character.damage(hp);
+            UpdateCharacterCommand::Damage { hp, damage_type, critical, .. } => {
+                let breakdown = if critical {
+                    character.critical_damage(hp, damage_type)
+                } else {
+                    character.damage(hp, damage_type)
+                };
+                Ok(CharacterCommandResponse::updated(character).with_damage(vec![breakdown]))
+            }
+            UpdateCharacterCommand::UpdatePlayer { player, .. } => {
+                character.player = player;
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::UpdateTieBreaker { roll, .. } => {
+                character.tie_breaker = Some(roll);
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::UpdateSurprised { surprised, .. } => {
+                character.surprised = surprised;
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::UpdateSkipTurn { skip, .. } => {
+                character.skip_turn = skip;
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::AddCondition { kind, duration, source, concentration, .. } => {
+                if concentration && source.is_none() {
+                    return Err(String::from("A condition held by concentration needs a source"));
+                }
+                let mut condition = Condition::new(kind, duration, source);
+                condition.concentration = concentration;
+                character.add_condition(condition);
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::RemoveCondition { condition, .. } => {
+                character.remove_condition(condition)?;
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::RollDeathSave { roll, .. } => {
+                character.roll_death_save(roll)?;
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::Stabilize { .. } => {
+                character.stabilize()?;
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::Revive { hp, .. } => {
+                character.revive(hp)?;
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::UpdateResistances { damage_types, .. } => {
+                character.set_resistances(damage_types);
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::UpdateVulnerabilities { damage_types, .. } => {
+                character.set_vulnerabilities(damage_types);
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::UpdateImmunities { damage_types, .. } => {
+                character.set_immunities(damage_types);
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::UpdateConcentration { spell, .. } => {
+                character.set_concentration(spell)?;
+                Ok(CharacterCommandResponse::updated(character))
+            }
+            UpdateCharacterCommand::UpdateArmorClass { armor_class, .. } => {
+                character.set_armor_class(armor_class)
+                    .map(|_| CharacterCommandResponse::updated(character))
+                    .or_else(|e| Ok(CharacterCommandResponse::updated_with_messages(character, CharacterChangeMessages::default().with_armor_class_error_message(e))))
+            }
+            UpdateCharacterCommand::UpdateAbilityScore { ability, score, .. } => Ok(Self::update_ability_score(character, ability, score)),
+            UpdateCharacterCommand::UpdateProficiencyBonus { bonus, .. } => {
+                character.set_proficiency_bonus(bonus)
+                    .map(|_| CharacterCommandResponse::updated(character))
+                    .or_else(|e| Ok(CharacterCommandResponse::updated_with_messages(character, CharacterChangeMessages::default().with_proficiency_bonus_error_message(e))))
+            }
+            UpdateCharacterCommand::UpdateSaveProficiencies { abilities, .. } => {
+                character.set_save_proficiencies(abilities);
                 Ok(CharacterCommandResponse::updated(character))
             }
         }
     }
 
-    pub fn start(&mut self) -> Result<(), String> {
-        match self.active_character.is_some() {
-            true => return Err(String::from("Encounter already started")),
-            false => self.active_character = self.characters.first().map(|c| c.uuid()),
+    fn update_ability_score(character: &mut Character, ability: Ability, score: i32) -> CharacterCommandResponse {
+        match character.set_ability_score(ability, score) {
+            Ok(()) => CharacterCommandResponse::updated(character),
+            Err(e) => CharacterCommandResponse::updated_with_messages(character, CharacterChangeMessages::default().with_ability_score_error_message(e)),
         }
-        self.last_active_character = None;
-        Ok(())
     }
 
-    pub fn stop(&mut self) -> Result<(), String> {
-        self.last_active_character = None;
-        self.active_character = None;
+    pub fn update_stage(&mut self, cmd: EncounterStageCmd) -> Result<(), String> {
+        // Ending a turn can wear off conditions, so undoing it has to restore the characters too
+        let inverse = self.roster_state();
+        self.skipped.clear();
+        self.expired.clear();
+        let result = self.apply_stage_command(cmd);
+        if result.is_err() {
+            self.swap_state(inverse)?;
+            return result;
+        }
+        self.record_change(inverse);
         Ok(())
     }
 
-    pub fn next(&mut self) -> Result<(), String> {
-        if let Some(id) = self.active_character {
-            let active_character_index = self.characters
-                .iter()
-                .position(|c| c.uuid() == id)
-                .ok_or(String::from("Active character not found"))?;
+    pub fn skipped_turns(&self) -> &[SkippedTurn] {
+        &self.skipped
+    }
 
-            let next_character_index = (active_character_index + 1) % self.characters.len();
-            self.active_character = Some(self.characters[next_character_index].uuid());
-            self.last_active_character = Some(id);
-        } else {
-            self.active_character = self.characters.first().map(|c| c.uuid());
-            self.last_active_character = None;
+    pub fn expired_conditions(&self) -> &[ExpiredCondition] {
+        &self.expired
+    }
+
+    fn apply_stage_command(&mut self, cmd: EncounterStageCmd) -> Result<(), String> {
+        match cmd {
+            EncounterStageCmd::Start => self.start()?,
+            EncounterStageCmd::StartWithSurprise => self.start_with_surprise()?,
+            EncounterStageCmd::Restart => self.restart()?,
+            EncounterStageCmd::Pause => self.pause()?,
+            EncounterStageCmd::Stop => self.stop()?,
+            EncounterStageCmd::Next => self.next()?,
+            EncounterStageCmd::Previous => self.previous()?,
+            EncounterStageCmd::Delay => self.delay()?,
+            EncounterStageCmd::Rejoin { character, initiative } => self.rejoin(character, initiative)?,
+            EncounterStageCmd::Ready { trigger } => self.ready(trigger)?,
+            EncounterStageCmd::TriggerReady { character } => self.trigger_ready(character)?,
         }
         Ok(())
     }
 
-    pub fn pause(&mut self) -> Result<() , String> {
-        self.last_active_character = self.active_character;
-        self.active_character = None;
-        Ok(())
+    pub fn undo(&mut self) -> Result<(), String> {
+        let change = self.history.pop_undo().ok_or("Nothing to undo")?;
+        match self.swap_state(change.clone()) {
+            Ok(redo) => {
+                self.history.push_redo(redo);
+                self.record_event(CombatEvent::Undone);
+                Ok(())
+            }
+            Err(e) => {
+                self.history.push_undo(change);
+                Err(e)
+            }
+        }
     }
 
-    pub fn restart(&mut self) -> Result<() , String> {
-        if let Some(id) = self.last_active_character {
-            self.active_character = Some(id);
-        } else {
-            self.active_character = self.characters.first().map(|c| c.uuid());
+    pub fn redo(&mut self) -> Result<(), String> {
+        let change = self.history.pop_redo().ok_or("Nothing to redo")?;
+        match self.swap_state(change.clone()) {
+            Ok(undo) => {
+                self.history.push_undo(undo);
+                self.record_event(CombatEvent::Redone);
+                Ok(())
+            }
+            Err(e) => {
+                self.history.push_redo(change);
+                Err(e)
+            }
         }
-        self.last_active_character = None;
-        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::encounters::*;
-    use crate::encounters::character::{CharacterChangeMessages, CharacterCommandResponse};
-    use crate::encounters::encounter::Encounter;
+    fn sort_characters(&mut self) {
+        if !self.manual_order {
+            self.tie_break.sort(&mut self.characters);
+        }
+        self.gather_groups();
+        self.sync_turn();
+    }
 
-    #[test]
-    fn test_new_encounter() {
-        let name = String::from("Test Encounter");
-        let encounter = Encounter::new(name.clone());
-        assert_eq!(encounter.name, name);
-        assert_eq!(encounter.characters.len(), 0);
-        assert_eq!(encounter.id().len(), 36);
+    fn gather_groups(&mut self) {
+        for group in self.groups.clone() {
+            let Some(anchor) = self.characters.iter().position(|c| group.contains(c.uuid())) else {
+                continue;
+            };
+            let (mut members, mut others): (Vec<Character>, Vec<Character>) = std::mem::take(&mut self.characters)
+                .into_iter()
+                .partition(|c| group.contains(c.uuid()));
+            members.sort_by_key(|c| group.members.iter().position(|id| *id == c.uuid()));
+            others.splice(anchor..anchor, members);
+            self.characters = others;
+        }
     }
 
-    #[test]
-    fn add_character() {
-        let mut encounter = Encounter::new(String::from("Test Encounter"));
-        let character = Character::new(String::from("Test Character"), 10, 10);
-        encounter.add_character(character.clone());
-        encounter.add_character(character.clone());
-        assert_eq!(encounter.get_characters().len(), 1);
+    fn group_of(&self, id: Uuid) -> Option<&CombatantGroup> {
+        self.groups.iter().find(|g| g.contains(id))
     }
 
-    #[test]
-    fn remove_character() {
-        let mut encounter = Encounter::new(String::from("Test Encounter"));
-        let character_a = Character::new("Character A", 10, 10);
-        let character_b = Character::new("Character B", 10, 5);
-        encounter.add_character(character_a.clone());
-        encounter.add_character(character_b.clone());
-        encounter.remove_character(character_a.clone());
-        assert_eq!(encounter.get_characters(), vec![character_b]);
+    fn share_initiative(&mut self, id: Uuid) {
+        let Some(group) = self.group_of(id).cloned() else {
+            return;
+        };
+        let Some(source) = self.characters.iter().find(|c| c.uuid() == id).cloned() else {
+            return;
+        };
+        for character in self.characters.iter_mut().filter(|c| group.contains(c.uuid())) {
+            character.set_initiative(source.initiative);
+            character.initiative_set = source.initiative_set;
+            character.tie_breaker = source.tie_breaker;
+        }
     }
 
-    #[test]
-    fn auto_sorts_new_characters() {
-        let mut encounter = Encounter::new(String::from("Test Encounter"));
-        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
-        let character2 = Character::new(String::from("Test Character 2"), 10, 20);
-        encounter.add_character(character2.clone());
-        encounter.add_character(character1.clone());
-        assert_eq!(encounter.get_characters()[0].name, character2.name);
-        assert_eq!(encounter.get_characters()[1].name, character1.name);
+    fn hand_over_slot(&mut self, from: Uuid, to: Uuid) {
+        let swap = |id: &mut Uuid| {
+            if *id == from {
+                *id = to;
+            }
+        };
+        self.active_character.iter_mut().for_each(swap);
+        self.last_active_character.iter_mut().for_each(swap);
+        self.acted.iter_mut().for_each(swap);
+        self.delayed.iter_mut().for_each(swap);
+        self.readied.iter_mut().for_each(|action| swap(&mut action.id));
+        self.skipped.iter_mut().for_each(|skipped| swap(&mut skipped.id));
+        self.passed_turns.iter_mut().for_each(|turn| swap(&mut turn.id));
     }
 
-    #[test]
-    fn find_character() {
-        let mut encounter = Encounter::new(String::from("Test Encounter"));
-        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
-        let character2 = Character::new(String::from("Test Character 2"), 10, 20);
-        encounter.add_character(character1.clone());
+    fn slot_initiative(&self, id: Uuid) -> Option<i32> {
+        match self.initiative_entries.iter().find(|e| e.id == id) {
+            Some(entry) => Some(entry.initiative),
+            None => self.characters.iter().find(|c| c.uuid() == id).map(|c| c.initiative),
+        }
+    }
 
-        assert_eq!(encounter.find_character(character1.id()).unwrap().name, character1.name);
-        assert_eq!(encounter.find_character(character2.id()), None);
+    // Nobody takes a lair's turn
+    fn slot_members(&self, id: Uuid) -> Vec<&Character> {
+        if let Some(entry) = self.initiative_entries.iter().find(|e| e.id == id) {
+            return self.characters.iter().filter(|c| Some(c.uuid()) == entry.character).collect();
+        }
+        match self.group_of(id) {
+            Some(group) => self.characters.iter().filter(|c| group.contains(c.uuid())).collect(),
+            None => self.characters.iter().filter(|c| c.uuid() == id).collect(),
+        }
     }
 
-    #[test]
-    fn update_character_command() {
-        let mut encounter = Encounter::new(String::from("Test Encounter"));
-        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
-        encounter.add_character(character1.clone());
+    fn position_of(&self, id: Uuid) -> Result<usize, String> {
+        self.characters
+            .iter()
+            .position(|c| c.uuid() == id)
+            .ok_or(format!("Character with id {} not found", id))
+    }
 
-        let cmd = character::UpdateCharacterCommand::UpdateName {
+    fn roster_state(&self) -> EncounterChange {
+        EncounterChange::Roster(RosterState {
+            characters: self.characters.clone(),
+            groups: self.groups.clone(),
+            entries: self.initiative_entries.clone(),
+            concentration_checks: self.concentration_checks.clone(),
+            manual_order: self.manual_order,
+            turn: self.turn_state(),
+        })
+    }
+
+    fn turn_state(&self) -> TurnState {
+        TurnState {
+            active_character: self.active_character,
+            last_active_character: self.last_active_character,
+            round: self.round,
+            turn: self.turn,
+            acted: self.acted.clone(),
+            delayed: self.delayed.clone(),
+            readied: self.readied.clone(),
+            surprise: self.surprise,
+            surprise_round: self.surprise_round,
+            skipped: self.skipped.clone(),
+            expired: self.expired.clone(),
+            passed_turns: self.passed_turns.clone(),
+        }
+    }
+
+    fn current_state_of(&self, change: &EncounterChange) -> Option<EncounterChange> {
+        match change {
+            EncounterChange::Character(character) => self.characters
+                .iter()
+                .find(|c| c.is_same_as(character))
+                .map(|c| EncounterChange::Character(c.clone())),
+            EncounterChange::Roster(_) => Some(self.roster_state()),
+            EncounterChange::Turn(_) => Some(EncounterChange::Turn(self.turn_state())),
+        }
+    }
+
+    fn record_change(&mut self, inverse: EncounterChange) {
+        if self.current_state_of(&inverse).as_ref() != Some(&inverse) {
+            self.history.record(inverse);
+        }
+    }
+
+    // Returns the state it replaced, so undo and redo can swap back and forth
+    fn swap_state(&mut self, change: EncounterChange) -> Result<EncounterChange, String> {
+        match change {
+            EncounterChange::Character(character) => {
+                let current = self.find_character_mut(character.uuid())
+                    .ok_or(format!("Character with id {} not found", character.uuid()))?;
+                let previous = std::mem::replace(current, character);
+                self.sort_characters();
+                Ok(EncounterChange::Character(previous))
+            }
+            EncounterChange::Roster(roster) => {
+                let current = self.roster_state();
+                self.characters = roster.characters;
+                self.groups = roster.groups;
+                self.initiative_entries = roster.entries;
+                self.concentration_checks = roster.concentration_checks;
+                self.manual_order = roster.manual_order;
+                self.swap_state(EncounterChange::Turn(roster.turn))?;
+                Ok(current)
+            }
+            EncounterChange::Turn(turn) => {
+                let current = self.turn_state();
+                self.active_character = turn.active_character;
+                self.last_active_character = turn.last_active_character;
+                self.round = turn.round;
+                self.turn = turn.turn;
+                self.acted = turn.acted;
+                self.delayed = turn.delayed;
+                self.readied = turn.readied;
+                self.surprise = turn.surprise;
+                self.surprise_round = turn.surprise_round;
+                self.skipped = turn.skipped;
+                self.expired = turn.expired;
+                self.passed_turns = turn.passed_turns;
+                self.sync_turn();
+                Ok(EncounterChange::Turn(current))
+            }
+        }
+    }
+
+    pub fn start(&mut self) -> Result<(), String> {
+        self.begin(false)
+    }
+
+    pub fn start_with_surprise(&mut self) -> Result<(), String> {
+        self.begin(true)
+    }
+
+    pub fn stop(&mut self) -> Result<(), String> {
+        self.last_active_character = None;
+        self.active_character = None;
+        self.clear_turn_order();
+        self.surprise = false;
+        self.surprise_round = false;
+        self.round = 0;
+        self.turn = 0;
+        Ok(())
+    }
+
+    // Takes the highest slot that has not acted, so initiative changes never cost or grant a turn
+    pub fn next(&mut self) -> Result<(), String> {
+        let before = self.conditions();
+        if let Some(id) = self.active_character {
+            if !self.characters.iter().any(|c| c.uuid() == id) && !self.initiative_entries.iter().any(|e| e.id == id) {
+                return Err(String::from("Active character not found"));
+            }
+            self.acted.push(id);
+            self.end_turn(id);
+            self.pass_turn(id)?;
+        } else {
+            self.acted.clear();
+            self.round = self.round.max(1);
+            let next = self.next_actor()?;
+            self.activate(next);
+            self.start_turn(next);
+            self.last_active_character = None;
+        }
+        self.remember_conditions(before);
+        Ok(())
+    }
+
+    pub fn previous(&mut self) -> Result<(), String> {
+        let id = self.active_character.ok_or("Encounter is not running")?;
+        // Puts back the countdowns and conditions the turn just handed over wore down
+        if self.passed_turns.last().is_some_and(|turn| turn.id == id && turn.round == self.round) {
+            if let Some(turn) = self.passed_turns.pop() {
+                self.restore_conditions(turn.conditions);
+            }
+        }
+        let rotation = self.rotation();
+        let active_index = rotation
+            .iter()
+            .position(|c| *c == id)
+            .ok_or(String::from("Active character not found"))?;
+
+        // Walks back over the same turns `next` passes over, so it never lands on someone who could not have acted
+        let mut index = active_index;
+        let previous = loop {
+            if index == 0 {
+                if self.round <= 1 {
+                    return Err(String::from("Already at the first turn of the encounter"));
+                }
+                // Everyone had their turn in the round before, lost turns included
+                self.round -= 1;
+                self.surprise = self.round == 1 && self.surprise_round;
+                self.acted = rotation.clone();
+                index = rotation.len();
+            }
+            index -= 1;
+            if self.skip_reason(rotation[index]).is_none() {
+                break rotation[index];
+            }
+        };
+        self.activate(Some(previous));
+        self.last_active_character = Some(id);
+        Ok(())
+    }
+
+    pub fn delay(&mut self) -> Result<(), String> {
+        let id = self.active_character.ok_or("Encounter is not running")?;
+        if self.rotation().len() < 2 {
+            return Err(String::from("Nobody else can act while this character delays"));
+        }
+        self.delayed.push(id);
+        self.pass_turn(id)
+    }
+
+    pub fn rejoin(&mut self, id: Uuid, initiative: i32) -> Result<(), String> {
+        if !self.delayed.contains(&id) {
+            return Err(String::from("Character is not delaying"));
+        }
+        if let Some(entry) = self.initiative_entries.iter_mut().find(|e| e.id == id) {
+            entry.initiative = initiative;
+        } else {
+            let character = self.find_character_mut(id).ok_or(format!("Character with id {} not found", id))?;
+            character.set_initiative(initiative);
+            self.share_initiative(id);
+        }
+        self.delayed.retain(|delayed| *delayed != id);
+        // A count that already came up this round has to wait for the next one
+        let passed = self.active_character
+            .and_then(|active| self.slot_initiative(active))
+            .is_some_and(|active| initiative > active);
+        if passed && !self.acted.contains(&id) {
+            self.acted.push(id);
+        }
+        self.sort_characters();
+        Ok(())
+    }
+
+    pub fn ready(&mut self, trigger: String) -> Result<(), String> {
+        let id = self.active_character.ok_or("Encounter is not running")?;
+        if trigger.trim().is_empty() {
+            return Err(String::from("Readied action needs a trigger"));
+        }
+        self.readied.retain(|action| action.id != id);
+        self.readied.push(ReadiedAction { id, trigger });
+        self.acted.push(id);
+        self.end_turn(id);
+        self.pass_turn(id)
+    }
+
+    pub fn trigger_ready(&mut self, id: Uuid) -> Result<(), String> {
+        let index = self.readied
+            .iter()
+            .position(|action| action.id == id)
+            .ok_or("Character has no readied action")?;
+        self.readied.remove(index);
+        Ok(())
+    }
+
+    pub fn pause(&mut self) -> Result<() , String> {
+        self.last_active_character = self.active_character;
+        self.active_character = None;
+        Ok(())
+    }
+
+    pub fn restart(&mut self) -> Result<() , String> {
+        if let Some(id) = self.last_active_character {
+            self.activate(Some(id));
+        } else {
+            self.acted.clear();
+            self.activate(self.rotation().first().copied());
+        }
+        self.round = self.round.max(1);
+        self.last_active_character = None;
+        Ok(())
+    }
+
+    fn pass_turn(&mut self, from: Uuid) -> Result<(), String> {
+        let next = self.next_actor()?;
+        self.activate(next);
+        self.start_turn(next);
+        self.last_active_character = Some(from);
+        Ok(())
+    }
+
+    fn begin(&mut self, surprise: bool) -> Result<(), String> {
+        if self.active_character.is_some() {
+            return Err(String::from("Encounter already started"));
+        }
+        self.clear_turn_order();
+        self.surprise = surprise;
+        self.surprise_round = surprise;
+        self.round = 1;
+        let first = self.next_actor()?;
+        self.activate(first);
+        self.start_turn(first);
+        self.last_active_character = None;
+        Ok(())
+    }
+
+    fn next_actor(&mut self) -> Result<Option<Uuid>, String> {
+        if self.characters.is_empty() {
+            return Ok(None);
+        }
+        let rotation = self.rotation();
+        if rotation.is_empty() {
+            return Err(String::from("Everyone is delaying"));
+        }
+        // Entries without members, such as lair actions, can always act
+        let stuck = |id: &Uuid| {
+            let members = self.slot_members(*id);
+            !members.is_empty() && members.iter().all(|c| c.is_down() || c.skip_turn)
+        };
+        if rotation.iter().all(stuck) {
+            return Err(String::from("Nobody in the encounter can act"));
+        }
+
+        loop {
+            let Some(id) = rotation.iter().copied().find(|id| !self.acted.contains(id)) else {
+                self.round += 1;
+                self.surprise = false;
+                self.acted.clear();
+                continue;
+            };
+            match self.skip_reason(id) {
+                Some(reason) => {
+                    let name = self.character_name(Some(id)).unwrap_or_default();
+                    self.skipped.push(SkippedTurn { id, name, reason });
+                    self.acted.push(id);
+                    // A lost turn still comes and goes, so durations tied to it run out as usual
+                    self.start_turn(Some(id));
+                    self.end_turn(id);
+                }
+                None => return Ok(Some(id)),
+            }
+        }
+    }
+
+    // Extra initiative entries belong to nobody, so durations run once a round
+    fn turn_owners(&self, slot: Uuid) -> Vec<Uuid> {
+        if self.initiative_entries.iter().any(|e| e.id == slot) {
+            return Vec::new();
+        }
+        self.slot_members(slot).iter().map(|c| c.uuid()).collect()
+    }
+
+    fn start_turn(&mut self, slot: Option<Uuid>) {
+        let Some(slot) = slot else {
+            return;
+        };
+        let owners = self.turn_owners(slot);
+        self.characters
+            .iter_mut()
+            .flat_map(|c| c.conditions.iter_mut())
+            .for_each(|condition| condition.start_turn(&owners));
+    }
+
+    fn end_turn(&mut self, slot: Uuid) {
+        let owners = self.turn_owners(slot);
+        for character in self.characters.iter_mut() {
+            let bearer = character.uuid();
+            let mut expired = Vec::new();
+            character.conditions.retain_mut(|condition| {
+                let ran_out = condition.end_turn(bearer, &owners);
+                if ran_out {
+                    expired.push(condition.clone());
+                }
+                !ran_out
+            });
+            self.expired.extend(expired.into_iter().map(|condition| ExpiredCondition { id: bearer, name: character.name.clone(), condition }));
+        }
+    }
+
+    fn conditions(&self) -> Vec<(Uuid, Condition)> {
+        self.characters
+            .iter()
+            .flat_map(|c| c.conditions.iter().map(|condition| (c.uuid(), condition.clone())))
+            .collect()
+    }
+
+    // Only conditions that changed are kept, so most turns leave nothing behind
+    fn remember_conditions(&mut self, before: Vec<(Uuid, Condition)>) {
+        let Some(id) = self.active_character else {
+            return;
+        };
+        let conditions: Vec<ConditionChange> = before
+            .into_iter()
+            .filter_map(|(bearer, condition)| {
+                let now = self.characters
+                    .iter()
+                    .find(|c| c.uuid() == bearer)
+                    .and_then(|c| c.conditions.iter().find(|c| c.id == condition.id));
+                match now {
+                    Some(now) if *now == condition => None,
+                    now => Some(ConditionChange { bearer, expired: now.is_none(), condition }),
+                }
+            })
+            .collect();
+        if conditions.is_empty() {
+            return;
+        }
+        self.passed_turns.push(PassedTurn { id, round: self.round, conditions });
+        if self.passed_turns.len() > HISTORY_LIMIT {
+            self.passed_turns.remove(0);
+        }
+    }
+
+    fn restore_conditions(&mut self, changes: Vec<ConditionChange>) {
+        for change in changes {
+            let Some(character) = self.find_character_mut(change.bearer) else {
+                continue;
+            };
+            match character.conditions.iter_mut().find(|c| c.id == change.condition.id) {
+                Some(condition) => *condition = change.condition,
+                None if change.expired => character.conditions.push(change.condition),
+                // Removed by hand since, so it stays gone
+                None => {}
+            }
+        }
+    }
+
+    // A group only loses its turn when none of its members can act
+    fn skip_reason(&self, id: Uuid) -> Option<SkipReason> {
+        let members = self.slot_members(id);
+        if members.is_empty() {
+            None
+        } else if members.iter().all(|c| c.is_down()) {
+            Some(SkipReason::Down)
+        } else if self.surprise && members.iter().all(|c| c.is_down() || c.surprised) {
+            Some(SkipReason::Surprised)
+        } else if members.iter().all(|c| c.is_down() || c.skip_turn) {
+            Some(SkipReason::SkipTurn)
+        } else {
+            None
+        }
+    }
+
+    // `is_none_or` would need Rust 1.82
+    #[allow(clippy::unnecessary_map_or)]
+    fn rotation(&self) -> Vec<Uuid> {
+        let mut entries: Vec<&InitiativeEntry> = self.initiative_entries.iter().collect();
+        entries.sort_by_key(|e| Reverse(e.initiative));
+        let mut entries = entries.into_iter().peekable();
+
+        let mut slots = Vec::new();
+        // Group members take their turn in the slot of their group
+        for character in self.characters.iter().filter(|c| self.group_of(c.uuid()).map_or(true, |g| g.leader() == c.uuid())) {
+            while let Some(entry) = entries.next_if(|e| e.initiative > character.initiative) {
+                slots.push(entry.id);
+            }
+            slots.push(character.uuid());
+        }
+        slots.extend(entries.map(|e| e.id));
+        slots.retain(|id| !self.delayed.contains(id));
+        slots
+    }
+
+    fn clear_turn_order(&mut self) {
+        self.acted.clear();
+        self.delayed.clear();
+        self.readied.clear();
+        self.passed_turns.clear();
+    }
+
+    fn activate(&mut self, id: Option<Uuid>) {
+        self.active_character = id;
+        if let Some(id) = id {
+            self.acted.retain(|acted| *acted != id);
+            // Readied actions are only held until the character's next turn
+            self.readied.retain(|action| action.id != id);
+        }
+        self.turn = 0;
+        self.sync_turn();
+    }
+
+    fn sync_turn(&mut self) {
+        // An extra turn points at its character, and a lair's turn leaves the position where it was
+        let id = self.active_character
+            .map(|id| self.initiative_entries.iter().find(|e| e.id == id).and_then(|e| e.character).unwrap_or(id));
+        if let Some(index) = id.and_then(|id| self.characters.iter().position(|c| c.uuid() == id)) {
+            self.turn = index as u32;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use uuid::Uuid;
+
+    use crate::encounters::*;
+    use crate::encounters::character::{Ability, CharacterChangeMessages, CharacterCommandResponse};
+    use crate::encounters::commands::RollInitiativeOptions;
+    use crate::encounters::encounter::Encounter;
+    use crate::encounters::initiative::{InitiativeEntry, SkipReason, SkippedTurn};
+    use crate::services::FrontendMessage;
+
+    #[test]
+    fn test_new_encounter() {
+        let name = String::from("Test Encounter");
+        let encounter = Encounter::new(name.clone());
+        assert_eq!(encounter.name, name);
+        assert_eq!(encounter.characters.len(), 0);
+        assert_eq!(encounter.id().len(), 36);
+    }
+
+    #[test]
+    fn add_character() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character = Character::new(String::from("Test Character"), 10, 10);
+        encounter.add_character(character.clone());
+        encounter.add_character(character.clone());
+        assert_eq!(encounter.get_characters().len(), 1);
+    }
+
+    #[test]
+    fn remove_character() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character_a = Character::new("Character A", 10, 10);
+        let character_b = Character::new("Character B", 10, 5);
+        encounter.add_character(character_a.clone());
+        encounter.add_character(character_b.clone());
+        encounter.remove_character(character_a.uuid()).unwrap();
+        assert_eq!(encounter.get_characters(), vec![character_b]);
+    }
+
+    #[test]
+    fn remove_active_character() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character1 = Character::new(String::from("Test Character 1"), 10, 20);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 15);
+        let character3 = Character::new(String::from("Test Character 3"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+        encounter.add_character(character3.clone());
+        encounter.start().unwrap();
+        encounter.next().unwrap();
+
+        encounter.remove_character(character2.uuid()).unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character3.uuid()));
+        assert_eq!(encounter.turn, 1);
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+        assert_eq!(encounter.round, 2);
+
+        encounter.undo().unwrap();
+        assert_eq!(encounter.get_characters().len(), 3);
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+
+        let missing = Uuid::new_v4();
+        assert_eq!(encounter.remove_character(missing), Err(format!("Character with id {} not found", missing)));
+    }
+
+    #[test]
+    fn duplicate_character() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let goblin = Character::new(String::from("Goblin"), 7, 12);
+        encounter.add_character(goblin.clone());
+        encounter.add_character(Character::new(String::from("Hero"), 10, 5));
+
+        let copy = encounter.duplicate_character(goblin.uuid()).unwrap();
+        assert_ne!(copy.uuid(), goblin.uuid());
+        assert_eq!(copy.name, "Goblin 2");
+        assert_eq!(copy.hp, goblin.hp);
+        let mut goblins: Vec<Uuid> = encounter.get_characters()[..2].iter().map(|c| c.uuid()).collect();
+        goblins.sort();
+        let mut expected = vec![goblin.uuid(), copy.uuid()];
+        expected.sort();
+        assert_eq!(goblins, expected);
+
+        let third = encounter.duplicate_character(copy.uuid()).unwrap();
+        assert_eq!(third.name, "Goblin 3");
+        assert_eq!(encounter.get_characters().len(), 4);
+
+        // A number that is part of the name is counted up from rather than replaced
+        let fifth = Character::new(String::from("Goblin 5"), 7, 12);
+        encounter.add_character(fifth.clone());
+        assert_eq!(encounter.duplicate_character(fifth.uuid()).unwrap().name, "Goblin 6");
+    }
+
+    #[test]
+    fn move_character() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character1 = Character::new(String::from("Test Character 1"), 10, 20);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 15);
+        let character3 = Character::new(String::from("Test Character 3"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+        encounter.add_character(character3.clone());
+        encounter.start().unwrap();
+
+        encounter.move_character(character3.uuid(), 0).unwrap();
+        assert_eq!(encounter.get_characters()[0].uuid(), character3.uuid());
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+        assert_eq!(encounter.turn, 1);
+        assert_eq!(encounter.move_character(character3.uuid(), 3), Err(String::from("Cannot move a character to position 4 of 3")));
+
+        // Newcomers slot in by initiative and later edits keep the manual order
+        let character4 = Character::new(String::from("Test Character 4"), 10, 18);
+        encounter.add_character(character4.clone());
+        assert_eq!(encounter.get_characters()[2].uuid(), character4.uuid());
+        let cmd = character::UpdateCharacterCommand::UpdateInitiative { id: character2.uuid(), initiative: 30 };
+        encounter.update_character(cmd).unwrap();
+        assert_eq!(encounter.get_characters()[3].uuid(), character2.uuid());
+
+        encounter.set_tie_break(initiative::TieBreakPolicy::Manual);
+        assert_eq!(encounter.get_characters()[0].uuid(), character2.uuid());
+    }
+
+    #[test]
+    fn auto_sorts_new_characters() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 20);
+        encounter.add_character(character2.clone());
+        encounter.add_character(character1.clone());
+        assert_eq!(encounter.get_characters()[0].name, character2.name);
+        assert_eq!(encounter.get_characters()[1].name, character1.name);
+    }
+
+    #[test]
+    fn find_character() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 20);
+        encounter.add_character(character1.clone());
+
+        assert_eq!(encounter.find_character(character1.id()).unwrap().name, character1.name);
+        assert_eq!(encounter.find_character(character2.id()), None);
+    }
+
+    #[test]
+    fn update_character_command() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
+        encounter.add_character(character1.clone());
+
+        let cmd = character::UpdateCharacterCommand::UpdateName {
             id: character1.uuid(),
             name: String::from("New Name"),
         };
 
         let response = encounter.update_character(cmd).unwrap();
         let updated_character = encounter.find_character(character1.id()).unwrap();
-        assert_eq!(response, CharacterCommandResponse::UpdatedCharacter { character: updated_character.clone(), messages: CharacterChangeMessages::none() });
+        assert_eq!(response, CharacterCommandResponse::UpdatedCharacter { character: Box::new(updated_character.clone()), messages: CharacterChangeMessages::none(), transitions: Vec::new(), damage: Vec::new(), concentration_checks: Vec::new() });
+
+        let cmd = character::UpdateCharacterCommand::UpdateInitiative {
+            id: character1.uuid(),
+            initiative: 20,
+        };
+
+        let response = encounter.update_character(cmd).unwrap();
+        let updated_character = encounter.find_character(character1.id()).unwrap();
+        assert_eq!(response, CharacterCommandResponse::UpdatedCharacter { character: Box::new(updated_character.clone()), messages: CharacterChangeMessages::none(), transitions: Vec::new(), damage: Vec::new(), concentration_checks: Vec::new() });
+    }
+
+    #[test]
+    fn zero_initiative_is_not_missing() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let mut unlucky = Character::new(String::from("Unlucky"), 10, 0);
+        unlucky.set_initiative(0);
+        let fresh = Character::new(String::from("Fresh"), 10, 0);
+        encounter.add_character(unlucky.clone());
+        encounter.add_character(fresh.clone());
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let options = commands::RollInitiativeOptions { only_missing: true, skip_players: false };
+        let rolls = encounter.roll_initiative(&options, &mut rng).unwrap();
+        assert_eq!(rolls.iter().map(|roll| roll.id).collect::<Vec<_>>(), vec![fresh.uuid()]);
+        assert_eq!(encounter.find_character(unlucky.id()).unwrap().initiative, 0);
+    }
+
+    #[test]
+    fn roll_initiative() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let mut player = Character::new(String::from("Player"), 10, 0);
+        player.player = true;
+        let mut monster = Character::new(String::from("Monster"), 10, 0);
+        monster.set_initiative_modifier(3);
+        let rolled = Character::new(String::from("Rolled"), 10, 12);
+        encounter.add_character(player.clone());
+        encounter.add_character(monster.clone());
+        encounter.add_character(rolled.clone());
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let options = commands::RollInitiativeOptions { only_missing: true, skip_players: true };
+        let rolls = encounter.roll_initiative(&options, &mut rng).unwrap();
+        assert_eq!(rolls.len(), 1);
+        assert_eq!(rolls[0].id, monster.uuid());
+        assert_eq!(rolls[0].roll.expression, "1d20+3");
+        assert_eq!(encounter.find_character(monster.id()).unwrap().initiative, rolls[0].roll.total);
+        assert_eq!(encounter.find_character(player.id()).unwrap().initiative, 0);
+
+        let rolls = encounter.roll_initiative(&commands::RollInitiativeOptions::default(), &mut rng).unwrap();
+        assert_eq!(rolls.len(), 3);
+        let mut sorted = encounter.get_characters();
+        sorted.sort();
+        assert_eq!(encounter.get_characters(), sorted);
+
+        // Rolling for everyone is a single undoable change
+        encounter.undo().unwrap();
+        assert_eq!(encounter.find_character(rolled.id()).unwrap().initiative, 12);
+        assert_eq!(encounter.find_character(player.id()).unwrap().initiative, 0);
+
+        // Tie-breakers are only rolled under their own policy, and never over one the DM set
+        assert!(encounter.get_characters().iter().all(|c| c.tie_breaker.is_none()));
+        encounter.set_tie_break(initiative::TieBreakPolicy::TieBreakerRoll);
+        let cmd = character::UpdateCharacterCommand::UpdateTieBreaker { id: player.uuid(), roll: 25 };
+        encounter.update_character(cmd).unwrap();
+        encounter.roll_initiative(&commands::RollInitiativeOptions::default(), &mut rng).unwrap();
+        assert_eq!(encounter.find_character(player.id()).unwrap().tie_breaker, Some(25));
+        assert!(encounter.get_characters().iter().all(|c| c.tie_breaker.is_some()));
+    }
+
+    #[test]
+    fn tie_break_policy() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let mut monster = Character::new(String::from("Monster"), 10, 15);
+        monster.ability_scores.dexterity = 12;
+        let mut player = Character::new(String::from("Player"), 10, 15);
+        player.player = true;
+        encounter.add_character(monster.clone());
+        encounter.add_character(player.clone());
+        assert_eq!(encounter.get_characters()[0].name, "Monster");
+
+        encounter.start().unwrap();
+        encounter.set_tie_break(initiative::TieBreakPolicy::PlayersFirst);
+        assert_eq!(encounter.get_characters()[0].name, "Player");
+        // The active combatant stays the same even though the order changed
+        assert_eq!(encounter.get_active_character_id(), Some(monster.uuid()));
+        assert_eq!(encounter.turn, 1);
+
+        let mut late_player = Character::new(String::from("Late Player"), 10, 15);
+        late_player.player = true;
+        encounter.add_character(late_player);
+        assert_eq!(encounter.get_characters()[2].name, "Monster");
+    }
+
+    #[test]
+    fn ties_go_to_the_higher_dexterity_by_default() {
+        let mut encounter = Encounter::new("Goblin Ambush");
+        let clumsy = Character::new("Clumsy", 10, 15);
+        let mut nimble = Character::new("Nimble", 10, 15);
+        nimble.ability_scores.dexterity = 16;
+        encounter.add_character(clumsy);
+        encounter.add_character(nimble);
+        assert_eq!(encounter.get_characters()[0].name, "Nimble");
+    }
+
+    #[test]
+    fn active_character() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        // These characters tie on everything, so they keep the order they were added in
+        encounter.set_tie_break(initiative::TieBreakPolicy::Manual);
+        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+
+        assert_eq!(encounter.get_active_character_id(), None);
+        // Uses First Character
+        encounter.start().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+
+        // Moves Next
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+
+        // Wraps
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+
+        let result = encounter.start();
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err(), String::from("Encounter already started"));
+    }
+
+    #[test]
+    fn tracks_round_and_turn() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        encounter.set_tie_break(initiative::TieBreakPolicy::Manual);
+        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+        assert_eq!((encounter.round, encounter.turn), (0, 0));
+
+        encounter.start().unwrap();
+        assert_eq!((encounter.round, encounter.turn), (1, 0));
+
+        encounter.next().unwrap();
+        assert_eq!((encounter.round, encounter.turn), (1, 1));
+
+        // Wrapping around starts a new round
+        encounter.next().unwrap();
+        assert_eq!((encounter.round, encounter.turn), (2, 0));
+        encounter.next().unwrap();
+        assert_eq!((encounter.round, encounter.turn), (2, 1));
+
+        encounter.pause().unwrap();
+        assert_eq!((encounter.round, encounter.turn), (2, 1));
+        encounter.restart().unwrap();
+        assert_eq!((encounter.round, encounter.turn), (2, 1));
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+
+        encounter.stop().unwrap();
+        assert_eq!((encounter.round, encounter.turn), (0, 0));
+    }
+
+    #[test]
+    fn previous_character() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        encounter.set_tie_break(initiative::TieBreakPolicy::Manual);
+        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+
+        assert_eq!(encounter.previous(), Err(String::from("Encounter is not running")));
+        encounter.start().unwrap();
+        assert_eq!(encounter.previous(), Err(String::from("Already at the first turn of the encounter")));
+
+        encounter.next().unwrap();
+        encounter.next().unwrap();
+        assert_eq!((encounter.round, encounter.turn), (2, 0));
+
+        // Wraps back into the previous round
+        encounter.previous().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+        assert_eq!((encounter.round, encounter.turn), (1, 1));
+
+        encounter.previous().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+        assert_eq!((encounter.round, encounter.turn), (1, 0));
+
+        encounter.pause().unwrap();
+        encounter.restart().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+        assert_eq!((encounter.round, encounter.turn), (1, 0));
+    }
+
+    #[test]
+    fn delay_and_rejoin() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character1 = Character::new(String::from("Test Character 1"), 10, 20);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 15);
+        let character3 = Character::new(String::from("Test Character 3"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+        encounter.add_character(character3.clone());
+
+        assert_eq!(encounter.delay(), Err(String::from("Encounter is not running")));
+        encounter.start().unwrap();
+        encounter.delay().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+
+        // Delaying characters are skipped, even into the next round
+        encounter.next().unwrap();
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+        assert_eq!(encounter.round, 2);
+
+        // Rejoining ahead of the remaining combatants means acting right after the current turn
+        assert_eq!(encounter.rejoin(character3.uuid(), 12), Err(String::from("Character is not delaying")));
+        encounter.rejoin(character1.uuid(), 12).unwrap();
+        assert_eq!(encounter.get_characters()[1].uuid(), character1.uuid());
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character3.uuid()));
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+        assert_eq!(encounter.round, 3);
+    }
+
+    #[test]
+    fn rejoining_at_a_passed_count_waits_for_the_next_round() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character1 = Character::new(String::from("Test Character 1"), 10, 20);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 15);
+        let character3 = Character::new(String::from("Test Character 3"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+        encounter.add_character(character3.clone());
+        encounter.start().unwrap();
+        encounter.delay().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+
+        encounter.rejoin(character1.uuid(), 18).unwrap();
+        assert_eq!(encounter.get_characters()[0].uuid(), character1.uuid());
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character3.uuid()));
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+        assert_eq!(encounter.round, 2);
+    }
+
+    #[test]
+    fn ready_and_trigger_actions() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character1 = Character::new(String::from("Test Character 1"), 10, 20);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 15);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+        encounter.start().unwrap();
+
+        assert_eq!(encounter.ready(String::from(" ")), Err(String::from("Readied action needs a trigger")));
+        encounter.ready(String::from("When the troll moves")).unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+        assert_eq!(encounter.readied[0].trigger, "When the troll moves");
+
+        encounter.trigger_ready(character1.uuid()).unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+        assert_eq!(encounter.trigger_ready(character1.uuid()), Err(String::from("Character has no readied action")));
+
+        // An unused readied action is lost when the character's next turn starts
+        encounter.previous().unwrap();
+        encounter.ready(String::from("When the door opens")).unwrap();
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+        assert!(encounter.readied.is_empty());
+    }
+
+    #[test]
+    fn surprise_round() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let mut character1 = Character::new(String::from("Test Character 1"), 10, 20);
+        character1.surprised = true;
+        let character2 = Character::new(String::from("Test Character 2"), 10, 15);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
 
-        let cmd = character::UpdateCharacterCommand::UpdateInitiative {
+        encounter.update_stage(commands::EncounterStageCmd::StartWithSurprise).unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+        assert_eq!(encounter.skipped_turns(), vec![initiative::SkippedTurn {
             id: character1.uuid(),
-            initiative: 20,
-        };
+            name: character1.name.clone(),
+            reason: initiative::SkipReason::Surprised,
+        }]);
 
-        let response = encounter.update_character(cmd).unwrap();
-        let updated_character = encounter.find_character(character1.id()).unwrap();
-        assert_eq!(response, CharacterCommandResponse::UpdatedCharacter { character: updated_character.clone(), messages: CharacterChangeMessages::none() });
+        // Surprise only lasts for the first round
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+        assert_eq!(encounter.round, 2);
+        assert!(encounter.skipped_turns().is_empty());
     }
 
     #[test]
-    fn active_character() {
+    fn previous_passes_over_characters_that_cannot_act() {
         let mut encounter = Encounter::new(String::from("Test Encounter"));
-        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
-        let character2 = Character::new(String::from("Test Character 2"), 10, 10);
+        let character1 = Character::new(String::from("Test Character 1"), 10, 20);
+        let mut character2 = Character::new(String::from("Test Character 2"), 10, 15);
+        character2.skip_turn = true;
+        let character3 = Character::new(String::from("Test Character 3"), 10, 10);
+        let character4 = Character::new(String::from("Test Character 4"), 10, 5);
         encounter.add_character(character1.clone());
         encounter.add_character(character2.clone());
+        encounter.add_character(character3.clone());
+        encounter.add_character(character4.clone());
+        encounter.update_stage(commands::EncounterStageCmd::Start).unwrap();
+        encounter.find_character_mut(character3.uuid()).unwrap().damage(10, None);
 
-        assert_eq!(encounter.get_active_character_id(), None);
-        // Uses First Character
-        encounter.start().unwrap();
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character4.uuid()));
+        encounter.update_stage(commands::EncounterStageCmd::Previous).unwrap();
         assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+        assert!(encounter.skipped_turns().is_empty());
+
+        // Going back into the last round also passes over the downed character at its end
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        assert_eq!(encounter.round, 2);
+        encounter.find_character_mut(character4.uuid()).unwrap().damage(10, None);
+        encounter.update_stage(commands::EncounterStageCmd::Previous).unwrap();
+        assert_eq!(encounter.round, 1);
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+    }
 
-        // Moves Next
-        encounter.next().unwrap();
+    #[test]
+    fn previous_brings_back_the_surprise_round() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let mut character1 = Character::new(String::from("Test Character 1"), 10, 20);
+        character1.surprised = true;
+        let character2 = Character::new(String::from("Test Character 2"), 10, 15);
+        let character3 = Character::new(String::from("Test Character 3"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+        encounter.add_character(character3.clone());
+        encounter.update_stage(commands::EncounterStageCmd::StartWithSurprise).unwrap();
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+        assert!(!encounter.surprise);
+
+        encounter.update_stage(commands::EncounterStageCmd::Previous).unwrap();
+        assert_eq!(encounter.round, 1);
+        assert!(encounter.surprise);
+        assert_eq!(encounter.get_active_character_id(), Some(character3.uuid()));
+        encounter.update_stage(commands::EncounterStageCmd::Previous).unwrap();
         assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+        // The surprised character lost its turn in round 1, so there is nothing further back
+        assert_eq!(
+            encounter.update_stage(commands::EncounterStageCmd::Previous),
+            Err(String::from("Already at the first turn of the encounter"))
+        );
+
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        assert_eq!(encounter.round, 2);
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+    }
 
-        // Wraps
-        encounter.next().unwrap();
+    #[test]
+    fn skips_characters_that_cannot_act() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character1 = Character::new(String::from("Test Character 1"), 10, 20);
+        let mut character2 = Character::new(String::from("Test Character 2"), 10, 15);
+        character2.skip_turn = true;
+        let character3 = Character::new(String::from("Test Character 3"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+        encounter.add_character(character3.clone());
+        encounter.update_stage(commands::EncounterStageCmd::Start).unwrap();
+
+        let cmd = character::UpdateCharacterCommand::Damage { id: character3.uuid(), hp: 10, damage_type: None, critical: false };
+        encounter.update_character(cmd).unwrap();
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
         assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+        assert_eq!(encounter.round, 2);
+        let reasons: Vec<initiative::SkipReason> = encounter.skipped_turns().iter().map(|s| s.reason).collect();
+        assert_eq!(reasons, vec![initiative::SkipReason::SkipTurn, initiative::SkipReason::Down]);
+
+        // Downed characters stay in the encounter and act again once healed
+        assert_eq!(encounter.get_characters().len(), 3);
+        let cmd = character::UpdateCharacterCommand::Heal { id: character3.uuid(), hp: 1 };
+        encounter.update_character(cmd).unwrap();
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character3.uuid()));
+
+        let cmd = character::UpdateCharacterCommand::Damage { id: character1.uuid(), hp: 10, damage_type: None, critical: false };
+        encounter.update_character(cmd).unwrap();
+        let cmd = character::UpdateCharacterCommand::Damage { id: character3.uuid(), hp: 10, damage_type: None, critical: false };
+        encounter.update_character(cmd).unwrap();
+        assert_eq!(encounter.update_stage(commands::EncounterStageCmd::Next), Err(String::from("Nobody in the encounter can act")));
+        assert_eq!(encounter.get_active_character_id(), Some(character3.uuid()));
+    }
+
+    #[test]
+    fn lair_actions_go_on_when_every_character_is_down() {
+        let mut encounter = Encounter::new("Castle Ravenloft");
+        let strahd = Character::new("Strahd", 40, 15);
+        let frodo = Character::new("Frodo", 8, 10);
+        encounter.add_character(strahd.clone());
+        encounter.add_character(frodo.clone());
+        let lair = encounter.add_initiative_entry(None, None, 20).unwrap();
+        encounter.start().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(lair.id));
+
+        for character in [&strahd, &frodo] {
+            let cmd = character::UpdateCharacterCommand::Damage { id: character.uuid(), hp: 100, damage_type: None, critical: false };
+            encounter.update_character(cmd).unwrap();
+        }
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(lair.id));
+        assert_eq!(encounter.round, 2);
+    }
+
+    #[test]
+    fn serializes_round_and_turn() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        encounter.add_character(Character::new(String::from("Test Character 1"), 10, 10));
+        encounter.start().unwrap();
+        encounter.next().unwrap();
+
+        let value = serde_json::to_value(&encounter).unwrap();
+        assert_eq!(value["round"], 2);
+        assert_eq!(value["turn"], 0);
+    }
 
+    #[test]
+    fn initiative_changes_resort_mid_round() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character1 = Character::new(String::from("Test Character 1"), 10, 20);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 15);
+        let character3 = Character::new(String::from("Test Character 3"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+        encounter.add_character(character3.clone());
+        encounter.start().unwrap();
         encounter.next().unwrap();
         assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
 
-        let result = encounter.start();
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), String::from("Encounter already started"));
+        // Character 3 moves ahead of the active slot but has not acted yet, so it still goes next
+        let cmd = character::UpdateCharacterCommand::UpdateInitiative { id: character3.uuid(), initiative: 25 };
+        encounter.update_character(cmd).unwrap();
+        assert_eq!(encounter.get_characters()[0].uuid(), character3.uuid());
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+        assert_eq!(encounter.turn, 2);
+
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character3.uuid()));
+        assert_eq!(encounter.round, 1);
+
+        // Character 1 already acted, so moving it to the bottom does not give it a second turn
+        let cmd = character::UpdateCharacterCommand::UpdateInitiative { id: character1.uuid(), initiative: 1 };
+        encounter.update_character(cmd).unwrap();
+        encounter.next().unwrap();
+        assert_eq!(encounter.round, 2);
+        assert_eq!(encounter.get_active_character_id(), Some(character3.uuid()));
+
+        encounter.next().unwrap();
+        encounter.next().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
     }
 
     #[test]
     fn next_character_sets_active_character() {
         let mut encounter = Encounter::new(String::from("Test Encounter"));
+        encounter.set_tie_break(initiative::TieBreakPolicy::Manual);
         let character1 = Character::new(String::from("Test Character 1"), 10, 10);
         let character2 = Character::new(String::from("Test Character 2"), 10, 10);
         encounter.add_character(character1.clone());
@@ -323,6 +2028,7 @@ mod tests {
     #[test]
     fn allow_restarting_a_character() {
         let mut encounter = Encounter::new(String::from("Test Encounter"));
+        encounter.set_tie_break(initiative::TieBreakPolicy::Manual);
         let character1 = Character::new(String::from("Test Character 1"), 10, 10);
         let character2 = Character::new(String::from("Test Character 2"), 10, 10);
         let character3 = Character::new(String::from("Test Character 3"), 10, 10);
@@ -345,6 +2051,7 @@ mod tests {
     #[test]
     fn restart_encounter() {
         let mut encounter = Encounter::new(String::from("Test Encounter"));
+        encounter.set_tie_break(initiative::TieBreakPolicy::Manual);
         let character1 = Character::new(String::from("Test Character 1"), 10, 10);
         let character2 = Character::new(String::from("Test Character 2"), 10, 10);
         encounter.add_character(character1.clone());
@@ -363,6 +2070,7 @@ mod tests {
     #[test]
     fn stop_encounter() {
         let mut encounter = Encounter::new(String::from("Test Encounter"));
+        encounter.set_tie_break(initiative::TieBreakPolicy::Manual);
         let character1 = Character::new(String::from("Test Character 1"), 10, 10);
         let character2 = Character::new(String::from("Test Character 2"), 10, 10);
         encounter.add_character(character1.clone());
@@ -380,4 +2088,710 @@ mod tests {
         encounter.restart().unwrap();
         assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
     }
+
+    #[test]
+    fn undo_and_redo_character_commands() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let mut character1 = Character::new(String::from("Test Character 1"), 10, 10);
+        character1.set_temporary_hp(3);
+        encounter.add_character(character1.clone());
+
+        // Temporary HP absorbs part of the damage and the rest is clamped at zero
+        let cmd = character::UpdateCharacterCommand::Damage { id: character1.uuid(), hp: 50, damage_type: None, critical: false };
+        encounter.update_character(cmd).unwrap();
+        let damaged = encounter.find_character(character1.id()).unwrap().clone();
+        assert_eq!(damaged.hp.current, 0);
+        assert_eq!(damaged.hp.temporary, 0);
+
+        encounter.undo().unwrap();
+        assert_eq!(encounter.find_character(character1.id()), Some(&character1));
+
+        encounter.redo().unwrap();
+        assert_eq!(encounter.find_character(character1.id()), Some(&damaged));
+
+        assert_eq!(encounter.redo(), Err(String::from("Nothing to redo")));
+        encounter.undo().unwrap();
+        assert_eq!(encounter.undo(), Err(String::from("Nothing to undo")));
+    }
+
+    #[test]
+    fn undo_skips_unchanged_commands() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
+        encounter.add_character(character1.clone());
+
+        let cmd = character::UpdateCharacterCommand::UpdateName { id: character1.uuid(), name: String::from("") };
+        encounter.update_character(cmd).unwrap();
+        assert_eq!(encounter.undo(), Err(String::from("Nothing to undo")));
+    }
+
+    #[test]
+    fn undo_and_redo_stage_commands() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        encounter.set_tie_break(initiative::TieBreakPolicy::Manual);
+        let character1 = Character::new(String::from("Test Character 1"), 10, 10);
+        let character2 = Character::new(String::from("Test Character 2"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+
+        encounter.update_stage(commands::EncounterStageCmd::Start).unwrap();
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+
+        encounter.undo().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character1.uuid()));
+
+        encounter.redo().unwrap();
+        assert_eq!(encounter.get_active_character_id(), Some(character2.uuid()));
+
+        // A new change drops whatever could have been redone
+        encounter.undo().unwrap();
+        encounter.update_stage(commands::EncounterStageCmd::Pause).unwrap();
+        assert_eq!(encounter.redo(), Err(String::from("Nothing to redo")));
+
+        encounter.undo().unwrap();
+        encounter.undo().unwrap();
+        assert_eq!(encounter.get_active_character_id(), None);
+    }
+
+    #[test]
+    fn logs_character_and_stage_changes() {
+        let mut encounter = Encounter::new(String::from("Test Encounter"));
+        encounter.set_tie_break(initiative::TieBreakPolicy::Manual);
+        let character1 = Character::new(String::from("Sam"), 10, 10);
+        let character2 = Character::new(String::from("Pippin"), 10, 10);
+        encounter.add_character(character1.clone());
+        encounter.add_character(character2.clone());
+
+        commands::EncounterCommandResponse::from_stage_command(&mut encounter, commands::EncounterStageCmd::Start).unwrap();
+        commands::EncounterCommandResponse::from_stage_command(&mut encounter, commands::EncounterStageCmd::Next).unwrap();
+        commands::EncounterCommandResponse::from_stage_command(&mut encounter, commands::EncounterStageCmd::Next).unwrap();
+        let cmd = character::UpdateCharacterCommand::Heal { id: character2.uuid(), hp: 5 };
+        encounter.update_character(cmd).unwrap();
+        let cmd = character::UpdateCharacterCommand::Damage { id: character2.uuid(), hp: 4, damage_type: None, critical: false };
+        encounter.update_character(cmd).unwrap();
+        encounter.undo().unwrap();
+
+        let log: Vec<String> = encounter.log().iter().map(|entry| entry.event.describe()).collect();
+        assert_eq!(log, vec![
+            "Sam joined the encounter",
+            "Pippin joined the encounter",
+            "Encounter started with Sam",
+            "Turn passed from Sam to Pippin",
+            "Turn passed from Pippin to Sam",
+            "Round 2 began",
+            "Pippin took 4 damage",
+            "Last change was undone",
+        ]);
+    }
+
+    #[test]
+    fn create_and_rename_encounters() {
+        let mut collection = EncounterCollection::new();
+        let id = collection.create_encounter("Lord of the Rings").uuid();
+        let other = collection.create_encounter("Harry Potter").uuid();
+
+        assert!(collection.validate_name("Weathertop", None).is_empty());
+        assert_eq!(collection.validate_name(" ", None).name, vec![FrontendMessage::error("Name cannot be empty")]);
+        assert_eq!(
+            collection.validate_name("Harry Potter", Some(id)).name,
+            vec![FrontendMessage::error("An encounter named Harry Potter already exists")],
+        );
+        assert!(collection.validate_name("Harry Potter", Some(other)).is_empty());
+
+        collection.rename_encounter(id, "Weathertop").unwrap();
+        assert_eq!(collection.find_encounter(id).unwrap().name(), "Weathertop");
+        assert!(collection.rename_encounter(Uuid::new_v4(), "Moria").is_err());
+    }
+
+    #[test]
+    fn clone_encounter_resets_combat() {
+        let mut collection = EncounterCollection::new();
+        let mut encounter = Encounter::new("Lord of the Rings");
+        let frodo = Character::new("Frodo", 8, 18);
+        encounter.add_character(frodo.clone());
+        encounter.add_character(Character::new("Sam", 6, 19));
+        encounter.start().unwrap();
+        encounter.next().unwrap();
+        collection.add_encounter(encounter.clone());
+
+        let copy = collection.clone_encounter(encounter.uuid()).unwrap().clone();
+        assert_eq!(copy.name(), "Lord of the Rings 2");
+        assert_ne!(copy.uuid(), encounter.uuid());
+        assert_eq!(copy.get_active_character_id(), None);
+        assert_eq!(copy.round(), 0);
+        assert!(copy.acted.is_empty());
+        assert!(copy.log().is_empty());
+
+        let names: Vec<&str> = copy.characters.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["Sam", "Frodo"]);
+        assert!(copy.characters.iter().all(|c| encounter.characters.iter().all(|o| o.uuid() != c.uuid())));
+        assert_eq!(copy.characters[1].hp, frodo.hp);
+
+        let third = collection.clone_encounter(copy.uuid()).unwrap();
+        assert_eq!(third.name(), "Lord of the Rings 3");
+        assert_eq!(collection.encounters.len(), 3);
+
+        let room = Encounter::new("Room 101");
+        collection.add_encounter(room.clone());
+        assert_eq!(collection.clone_encounter(room.uuid()).unwrap().name(), "Room 102");
+    }
+
+    #[test]
+    fn delete_encounter() {
+        let mut collection = EncounterCollection::new();
+        let id = collection.create_encounter("Lord of the Rings").uuid();
+
+        assert_eq!(collection.delete_encounter(id).unwrap().name(), "Lord of the Rings");
+        assert!(collection.encounters.is_empty());
+        assert_eq!(collection.delete_encounter(id), Err("Encounter not found".to_string()));
+    }
+
+    fn goblin_encounter() -> (Encounter, Character, Character, Character, Character) {
+        let mut encounter = Encounter::new("Goblin Ambush");
+        let frodo = Character::new("Frodo", 8, 15);
+        let goblin_a = Character::new("Goblin A", 7, 12);
+        let goblin_b = Character::new("Goblin B", 7, 10);
+        let goblin_c = Character::new("Goblin C", 7, 8);
+        for character in [&frodo, &goblin_a, &goblin_b, &goblin_c] {
+            encounter.add_character(character.clone());
+        }
+        (encounter, frodo, goblin_a, goblin_b, goblin_c)
+    }
+
+    #[test]
+    fn group_takes_one_turn() {
+        let (mut encounter, frodo, goblin_a, goblin_b, goblin_c) = goblin_encounter();
+        let group = encounter.group_characters("Goblins", &[goblin_c.uuid(), goblin_a.uuid()]).unwrap();
+        assert_eq!(group.members, vec![goblin_a.uuid(), goblin_c.uuid()]);
+
+        let order: Vec<&str> = encounter.characters.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(order, vec!["Frodo", "Goblin A", "Goblin C", "Goblin B"]);
+        assert!(encounter.characters.iter().filter(|c| group.contains(c.uuid())).all(|c| c.initiative == 12));
+
+        encounter.start().unwrap();
+        assert_eq!(encounter.active_character, Some(frodo.uuid()));
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(goblin_a.uuid()));
+        assert_eq!(encounter.character_name(encounter.active_character), Some(String::from("Goblins")));
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(goblin_b.uuid()));
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(frodo.uuid()));
+        assert_eq!(encounter.round, 2);
+
+        let document = serde_json::to_value(&encounter).unwrap();
+        assert_eq!(document["groups"][0]["members"][1], goblin_c.id());
+    }
+
+    #[test]
+    fn group_is_skipped_once_everyone_is_down() {
+        let (mut encounter, frodo, goblin_a, _, goblin_c) = goblin_encounter();
+        encounter.group_characters("Goblins", &[goblin_a.uuid(), goblin_c.uuid()]).unwrap();
+        encounter.find_character_mut(goblin_a.uuid()).unwrap().damage(7, None);
+
+        encounter.start().unwrap();
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(goblin_a.uuid()), "Goblin C can still act for the group");
+
+        encounter.find_character_mut(goblin_c.uuid()).unwrap().damage(7, None);
+        encounter.next().unwrap();
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(frodo.uuid()));
+        encounter.next().unwrap();
+        assert_eq!(encounter.skipped_turns(), &[SkippedTurn { id: goblin_a.uuid(), name: String::from("Goblins"), reason: SkipReason::Down }]);
+    }
+
+    #[test]
+    fn damage_and_heal_a_whole_group() {
+        let (mut encounter, _, goblin_a, goblin_b, goblin_c) = goblin_encounter();
+        let group = encounter.group_characters("Goblins", &[goblin_a.uuid(), goblin_c.uuid()]).unwrap();
+
+        let response = encounter.update_character(character::UpdateCharacterCommand::Damage { id: group.id, hp: 3, damage_type: None, critical: false }).unwrap();
+        let CharacterCommandResponse::UpdatedGroup { characters, damage, .. } = response else {
+            panic!("expected the whole group to be updated");
+        };
+        assert!(characters.iter().all(|c| c.hp.current == 4));
+        assert_eq!(damage.len(), 2);
+        assert_eq!(encounter.find_character_mut(goblin_b.uuid()).unwrap().hp.current, 7);
+
+        encounter.update_character(character::UpdateCharacterCommand::Heal { id: goblin_c.uuid(), hp: 2 }).unwrap();
+        assert_eq!(encounter.find_character_mut(goblin_a.uuid()).unwrap().hp.current, 4);
+        assert_eq!(encounter.find_character_mut(goblin_c.uuid()).unwrap().hp.current, 6);
+
+        encounter.undo().unwrap();
+        encounter.undo().unwrap();
+        assert!(encounter.characters.iter().filter(|c| c.name.starts_with("Goblin")).all(|c| c.hp.current == 7));
+
+        let cmd = character::UpdateCharacterCommand::UpdateName { id: group.id, name: String::from("Orcs") };
+        assert!(encounter.update_character(cmd).is_err());
+    }
+
+    #[test]
+    fn group_members_share_initiative() {
+        let (mut encounter, _, goblin_a, goblin_b, goblin_c) = goblin_encounter();
+        encounter.group_characters("Goblins", &[goblin_a.uuid(), goblin_c.uuid()]).unwrap();
+
+        encounter.update_character(character::UpdateCharacterCommand::UpdateInitiative { id: goblin_c.uuid(), initiative: 20 }).unwrap();
+        let order: Vec<&str> = encounter.characters.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(order, vec!["Goblin A", "Goblin C", "Frodo", "Goblin B"]);
+
+        let rolls = encounter.roll_initiative(&RollInitiativeOptions::default(), &mut StdRng::seed_from_u64(7)).unwrap();
+        assert!(rolls.iter().all(|roll| roll.id != goblin_c.uuid()));
+        let initiative = |id: Uuid| encounter.characters.iter().find(|c| c.uuid() == id).unwrap().initiative;
+        assert_eq!(initiative(goblin_a.uuid()), initiative(goblin_c.uuid()));
+        assert!(rolls.iter().any(|roll| roll.id == goblin_b.uuid()));
+    }
+
+    #[test]
+    fn removing_the_slot_holder_keeps_the_group_turn() {
+        let (mut encounter, _, goblin_a, goblin_b, goblin_c) = goblin_encounter();
+        let goblin_d = Character::new("Goblin D", 7, 1);
+        encounter.add_character(goblin_d.clone());
+        encounter.group_characters("Goblins", &[goblin_a.uuid(), goblin_c.uuid(), goblin_d.uuid()]).unwrap();
+        encounter.start().unwrap();
+        encounter.next().unwrap();
+
+        encounter.remove_character(goblin_a.uuid()).unwrap();
+        assert_eq!(encounter.active_character, Some(goblin_c.uuid()));
+        assert_eq!(encounter.groups[0].members, vec![goblin_c.uuid(), goblin_d.uuid()]);
+
+        encounter.remove_character(goblin_d.uuid()).unwrap();
+        assert!(encounter.groups.is_empty());
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(goblin_b.uuid()));
+    }
+
+    #[test]
+    fn ungrouped_members_keep_their_place_in_the_round() {
+        let (mut encounter, frodo, goblin_a, goblin_b, goblin_c) = goblin_encounter();
+        let group = encounter.group_characters("Goblins", &[goblin_a.uuid(), goblin_c.uuid()]).unwrap();
+        encounter.start().unwrap();
+        encounter.next().unwrap();
+
+        let removed = encounter.ungroup_characters(group.id).unwrap();
+        assert_eq!(removed, group);
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(goblin_b.uuid()));
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(frodo.uuid()));
+
+        assert_eq!(encounter.ungroup_characters(group.id), Err(String::from("Group not found")));
+        encounter.undo().unwrap();
+        assert_eq!(encounter.groups, vec![group]);
+    }
+
+    #[test]
+    fn cloned_encounter_keeps_its_groups() {
+        let (mut encounter, _, goblin_a, _, goblin_c) = goblin_encounter();
+        let group = encounter.group_characters("Goblins", &[goblin_a.uuid(), goblin_c.uuid()]).unwrap();
+
+        let copy = encounter.duplicate("Goblin Ambush 2");
+        assert_eq!(copy.groups.len(), 1);
+        assert_ne!(copy.groups[0].id, group.id);
+        assert_eq!(copy.groups[0].members.len(), 2);
+        assert!(copy.groups[0].members.iter().all(|id| copy.characters.iter().any(|c| c.uuid() == *id)));
+    }
+
+    #[test]
+    fn cloned_encounter_drops_dangling_references() {
+        let (mut encounter, frodo, goblin_a, goblin_b, _) = goblin_encounter();
+        encounter.group_characters("Goblins", &[goblin_a.uuid(), goblin_b.uuid()]).unwrap();
+        encounter.add_initiative_entry(None, None, 20).unwrap();
+        encounter.add_initiative_entry(None, Some(frodo.uuid()), 10).unwrap();
+        // Hand-edited files can point at characters that are not in the encounter
+        encounter.groups[0].members.push(Uuid::new_v4());
+        encounter.initiative_entries.push(InitiativeEntry { id: Uuid::new_v4(), name: String::from("Nobody"), initiative: 5, character: Some(Uuid::new_v4()) });
+
+        let copy = encounter.duplicate("Goblin Ambush 2");
+        assert_eq!(copy.groups[0].members.len(), 2);
+        assert_eq!(copy.initiative_entries.len(), 2);
+        assert!(copy.initiative_entries.iter().any(|e| e.character.is_none()));
+        assert!(copy.initiative_entries.iter().filter_map(|e| e.character).all(|id| copy.characters.iter().any(|c| c.uuid() == id)));
+    }
+
+    #[test]
+    fn copied_conditions_follow_the_copied_characters() {
+        let (mut encounter, frodo, goblin_a, goblin_b, _) = goblin_encounter();
+        let frightened = character::UpdateCharacterCommand::AddCondition {
+            id: goblin_a.uuid(),
+            kind: character::ConditionKind::Frightened,
+            duration: character::ConditionDuration::EndOfTurn { character: frodo.uuid(), started: false },
+            source: Some(frodo.uuid()),
+            concentration: true,
+        };
+        encounter.update_character(frightened).unwrap();
+        let hexed = character::UpdateCharacterCommand::AddCondition {
+            id: goblin_b.uuid(),
+            kind: character::ConditionKind::Other(String::from("Hexed")),
+            duration: character::ConditionDuration::UntilRemoved,
+            source: Some(Uuid::new_v4()),
+            concentration: false,
+        };
+        encounter.update_character(hexed).unwrap();
+
+        let copy = encounter.duplicate("Goblin Ambush 2");
+        let copied_frodo = copy.characters.iter().find(|c| c.name == "Frodo").unwrap().uuid();
+        let condition = &copy.characters.iter().find(|c| c.name == "Goblin A").unwrap().conditions[0];
+        assert_eq!(condition.source, Some(copied_frodo));
+        assert_eq!(condition.duration, character::ConditionDuration::EndOfTurn { character: copied_frodo, started: false });
+        // A source that is not in the encounter cannot be followed into the copy
+        assert!(copy.characters.iter().find(|c| c.name == "Goblin B").unwrap().conditions.is_empty());
+
+        // A duplicated character keeps conditions from others, and takes over the ones tied to the original
+        let dodging = character::UpdateCharacterCommand::AddCondition {
+            id: frodo.uuid(),
+            kind: character::ConditionKind::Other(String::from("Dodging")),
+            duration: character::ConditionDuration::EndOfTurn { character: frodo.uuid(), started: false },
+            source: Some(frodo.uuid()),
+            concentration: false,
+        };
+        encounter.update_character(dodging).unwrap();
+        let goblin_copy = encounter.duplicate_character(goblin_a.uuid()).unwrap();
+        assert_eq!(goblin_copy.conditions[0].source, Some(frodo.uuid()));
+        let frodo_copy = encounter.duplicate_character(frodo.uuid()).unwrap();
+        assert_eq!(frodo_copy.conditions[0].source, Some(frodo_copy.uuid()));
+        assert_eq!(frodo_copy.conditions[0].duration, character::ConditionDuration::EndOfTurn { character: frodo_copy.uuid(), started: false });
+    }
+
+    #[test]
+    fn extra_initiative_entries_take_turns() {
+        let mut encounter = Encounter::new("Castle Ravenloft");
+        let strahd = Character::new("Strahd", 40, 20);
+        let frodo = Character::new("Frodo", 8, 22);
+        let sam = Character::new("Sam", 6, 5);
+        for character in [&strahd, &frodo, &sam] {
+            encounter.add_character(character.clone());
+        }
+        let lair = encounter.add_initiative_entry(None, None, 20).unwrap();
+        let second = encounter.add_initiative_entry(None, Some(strahd.uuid()), 10).unwrap();
+        assert_eq!(lair.name, "Lair");
+        assert_eq!(second.name, "Strahd");
+
+        encounter.start().unwrap();
+        let mut turns = Vec::new();
+        for _ in 0..5 {
+            turns.push(encounter.active_character.unwrap());
+            encounter.next().unwrap();
+        }
+        // The lair loses its tie with Strahd
+        assert_eq!(turns, vec![frodo.uuid(), strahd.uuid(), lair.id, second.id, sam.uuid()]);
+        assert_eq!(encounter.active_character, Some(frodo.uuid()));
+        assert_eq!(encounter.round, 2);
+
+        encounter.next().unwrap();
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(lair.id));
+        assert_eq!(encounter.character_name(encounter.active_character), Some(String::from("Lair")));
+        encounter.pause().unwrap();
+        encounter.restart().unwrap();
+        assert_eq!(encounter.active_character, Some(lair.id));
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(second.id));
+        assert_eq!(encounter.turn, 1, "the extra turn points at Strahd's row");
+    }
+
+    #[test]
+    fn extra_turns_are_skipped_with_their_character() {
+        let mut encounter = Encounter::new("Castle Ravenloft");
+        let strahd = Character::new("Strahd", 40, 20);
+        let frodo = Character::new("Frodo", 8, 22);
+        encounter.add_character(strahd.clone());
+        encounter.add_character(frodo.clone());
+        encounter.add_initiative_entry(Some(String::from("Strahd again")), Some(strahd.uuid()), 10).unwrap();
+
+        encounter.find_character_mut(strahd.uuid()).unwrap().damage(40, None);
+        encounter.start().unwrap();
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(frodo.uuid()));
+        let skipped: Vec<&str> = encounter.skipped_turns().iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(skipped, vec!["Strahd", "Strahd again"]);
+    }
+
+    #[test]
+    fn removing_entries() {
+        let mut encounter = Encounter::new("Castle Ravenloft");
+        let strahd = Character::new("Strahd", 40, 20);
+        let frodo = Character::new("Frodo", 8, 22);
+        encounter.add_character(strahd.clone());
+        encounter.add_character(frodo.clone());
+        let lair = encounter.add_initiative_entry(None, None, 20).unwrap();
+        encounter.add_initiative_entry(None, Some(strahd.uuid()), 10).unwrap();
+
+        encounter.start().unwrap();
+        encounter.next().unwrap();
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(lair.id));
+        encounter.remove_initiative_entry(lair.id).unwrap();
+        assert_eq!(encounter.active_character, Some(encounter.initiative_entries[0].id));
+
+        encounter.remove_character(strahd.uuid()).unwrap();
+        assert!(encounter.initiative_entries.is_empty());
+        assert_eq!(encounter.active_character, Some(frodo.uuid()));
+
+        encounter.undo().unwrap();
+        encounter.undo().unwrap();
+        assert_eq!(encounter.initiative_entries.len(), 2);
+        assert_eq!(encounter.active_character, Some(lair.id));
+        assert_eq!(encounter.remove_initiative_entry(Uuid::new_v4()), Err(String::from("Initiative entry not found")));
+        assert!(encounter.add_initiative_entry(None, Some(Uuid::new_v4()), 10).is_err());
+    }
+
+    #[test]
+    fn conditions_expire_as_turns_end() {
+        let mut encounter = Encounter::new("Lord of the Rings");
+        let frodo = Character::new("Frodo", 8, 18);
+        let sam = Character::new("Sam", 6, 12);
+        encounter.add_character(frodo.clone());
+        encounter.add_character(sam.clone());
+        encounter.update_stage(commands::EncounterStageCmd::Start).unwrap();
+
+        let poisoned = character::UpdateCharacterCommand::AddCondition {
+            id: sam.uuid(),
+            kind: character::ConditionKind::Poisoned,
+            duration: character::ConditionDuration::Rounds { rounds: 1 },
+            source: None,
+            concentration: false,
+        };
+        encounter.update_character(poisoned).unwrap();
+        // Frodo frightens Sam during Frodo's own turn, so it lasts through Frodo's next turn
+        let frightened = character::UpdateCharacterCommand::AddCondition {
+            id: sam.uuid(),
+            kind: character::ConditionKind::Frightened,
+            duration: character::ConditionDuration::EndOfTurn { character: frodo.uuid(), started: false },
+            source: Some(frodo.uuid()),
+            concentration: false,
+        };
+        encounter.update_character(frightened).unwrap();
+
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        assert!(encounter.expired_conditions().is_empty());
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        let expired: Vec<&str> = encounter.expired_conditions().iter().map(|e| e.condition.kind.name()).collect();
+        assert_eq!(expired, vec!["poisoned"]);
+        assert_eq!(encounter.expired_conditions()[0].id, sam.uuid());
+
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        let expired: Vec<&str> = encounter.expired_conditions().iter().map(|e| e.condition.kind.name()).collect();
+        assert_eq!(expired, vec!["frightened"]);
+        assert!(encounter.characters.iter().all(|c| c.conditions.is_empty()));
+
+        encounter.undo().unwrap();
+        let sam_now = encounter.characters.iter().find(|c| c.uuid() == sam.uuid()).unwrap();
+        assert_eq!(sam_now.conditions.len(), 1);
+        assert!(encounter.expired_conditions().iter().any(|e| e.condition.kind == character::ConditionKind::Poisoned));
+    }
+
+    #[test]
+    fn previous_winds_conditions_back() {
+        let mut encounter = Encounter::new("Lord of the Rings");
+        let frodo = Character::new("Frodo", 8, 18);
+        let sam = Character::new("Sam", 6, 12);
+        encounter.add_character(frodo.clone());
+        encounter.add_character(sam.clone());
+        encounter.update_stage(commands::EncounterStageCmd::Start).unwrap();
+        let poisoned = character::UpdateCharacterCommand::AddCondition {
+            id: frodo.uuid(),
+            kind: character::ConditionKind::Poisoned,
+            duration: character::ConditionDuration::Rounds { rounds: 2 },
+            source: None,
+            concentration: false,
+        };
+        encounter.update_character(poisoned).unwrap();
+        let frightened = character::UpdateCharacterCommand::AddCondition {
+            id: frodo.uuid(),
+            kind: character::ConditionKind::Frightened,
+            duration: character::ConditionDuration::EndOfTurn { character: sam.uuid(), started: false },
+            source: Some(sam.uuid()),
+            concentration: false,
+        };
+        encounter.update_character(frightened).unwrap();
+        let durations = |encounter: &Encounter| -> Vec<character::ConditionDuration> {
+            encounter.characters[0].conditions.iter().map(|c| c.duration.clone()).collect()
+        };
+        let fresh = durations(&encounter);
+
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        encounter.update_stage(commands::EncounterStageCmd::Previous).unwrap();
+        assert_eq!(durations(&encounter), fresh);
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        assert_eq!(durations(&encounter), vec![
+            character::ConditionDuration::Rounds { rounds: 1 },
+            character::ConditionDuration::EndOfTurn { character: sam.uuid(), started: true },
+        ]);
+
+        // Frightened wears off at the end of Sam's turn and poisoned at the end of Frodo's next one
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        encounter.update_stage(commands::EncounterStageCmd::Next).unwrap();
+        assert!(encounter.characters[0].conditions.is_empty());
+        encounter.update_stage(commands::EncounterStageCmd::Previous).unwrap();
+        assert_eq!(encounter.active_character, Some(frodo.uuid()));
+        assert_eq!(durations(&encounter), vec![character::ConditionDuration::Rounds { rounds: 1 }]);
+        encounter.update_stage(commands::EncounterStageCmd::Previous).unwrap();
+        assert_eq!(durations(&encounter).len(), 2);
+    }
+
+    #[test]
+    fn expired_conditions_are_logged() {
+        let mut encounter = Encounter::new("Lord of the Rings");
+        let frodo = Character::new("Frodo", 8, 18);
+        encounter.add_character(frodo.clone());
+        encounter.add_character(Character::new("Sam", 6, 12));
+        let prone = character::UpdateCharacterCommand::AddCondition {
+            id: frodo.uuid(),
+            kind: character::ConditionKind::Prone,
+            duration: character::ConditionDuration::Rounds { rounds: 1 },
+            source: None,
+            concentration: false,
+        };
+        encounter.update_character(prone).unwrap();
+        commands::EncounterCommandResponse::from_stage_command(&mut encounter, commands::EncounterStageCmd::Start).unwrap();
+        commands::EncounterCommandResponse::from_stage_command(&mut encounter, commands::EncounterStageCmd::Next).unwrap();
+
+        assert!(encounter.characters[0].conditions.is_empty());
+        let log: Vec<String> = encounter.log().iter().map(|entry| entry.event.describe()).collect();
+        assert_eq!(&log[2..], &[
+            "Frodo is now prone",
+            "Encounter started with Frodo",
+            "Turn passed from Frodo to Sam",
+            "Frodo is no longer prone as the effect wore off",
+        ]);
+
+        let cmd = character::UpdateCharacterCommand::RemoveCondition { id: frodo.uuid(), condition: Uuid::new_v4() };
+        assert_eq!(encounter.update_character(cmd), Err(String::from("Condition not found")));
+    }
+
+    #[test]
+    fn dying_players_roll_death_saves_on_their_turn() {
+        let mut encounter = Encounter::new("Lord of the Rings");
+        let mut frodo = Character::new("Frodo", 8, 18);
+        frodo.player = true;
+        let orc = Character::new("Orc", 15, 12);
+        encounter.add_character(frodo.clone());
+        encounter.add_character(orc.clone());
+        encounter.start().unwrap();
+        encounter.next().unwrap();
+
+        let damage = character::UpdateCharacterCommand::Damage { id: frodo.uuid(), hp: 9, damage_type: None, critical: false };
+        let CharacterCommandResponse::UpdatedCharacter { transitions, .. } = encounter.update_character(damage).unwrap() else {
+            panic!("expected a single character to be updated");
+        };
+        assert_eq!(transitions, vec![character::LifeStateChange {
+            id: frodo.uuid(),
+            name: String::from("Frodo"),
+            from: character::LifeState::Alive,
+            to: character::LifeState::Dying,
+        }]);
+
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(frodo.uuid()));
+        encounter.update_character(character::UpdateCharacterCommand::Stabilize { id: frodo.uuid() }).unwrap();
+        encounter.next().unwrap();
+        encounter.next().unwrap();
+        assert_eq!(encounter.active_character, Some(orc.uuid()), "stable characters are down");
+
+        let log: Vec<String> = encounter.log().iter().map(|entry| entry.event.describe()).collect();
+        assert!(log.contains(&String::from("Frodo is dying")));
+        assert!(log.contains(&String::from("Frodo is stable")));
+    }
+
+    #[test]
+    fn concentration_checks_follow_damage() {
+        let mut encounter = Encounter::new("Lord of the Rings");
+        let gandalf = Character::new("Gandalf", 60, 15);
+        let orc = Character::new("Orc", 15, 12);
+        encounter.add_character(gandalf.clone());
+        encounter.add_character(orc.clone());
+        let concentrate = character::UpdateCharacterCommand::UpdateConcentration { id: gandalf.uuid(), spell: Some(String::from("Hold Person")) };
+        encounter.update_character(concentrate).unwrap();
+        let paralyzed = character::UpdateCharacterCommand::AddCondition {
+            id: orc.uuid(),
+            kind: character::ConditionKind::Paralyzed,
+            duration: character::ConditionDuration::default(),
+            source: Some(gandalf.uuid()),
+            concentration: true,
+        };
+        encounter.update_character(paralyzed).unwrap();
+
+        let damage = character::UpdateCharacterCommand::Damage { id: gandalf.uuid(), hp: 24, damage_type: None, critical: false };
+        let CharacterCommandResponse::UpdatedCharacter { concentration_checks, .. } = encounter.update_character(damage.clone()).unwrap() else {
+            panic!("expected a single character to be updated");
+        };
+        assert_eq!(concentration_checks.len(), 1);
+        assert_eq!(concentration_checks[0].dc, 12);
+        assert_eq!(encounter.concentration_checks, concentration_checks);
+
+        encounter.resolve_concentration_check(concentration_checks[0].id, true).unwrap();
+        assert!(encounter.concentration_checks.is_empty());
+        assert_eq!(encounter.characters[1].conditions.len(), 1);
+
+        encounter.update_character(damage).unwrap();
+        let check = encounter.concentration_checks[0].id;
+        encounter.resolve_concentration_check(check, false).unwrap();
+        assert_eq!(encounter.characters[0].concentration, None);
+        assert!(encounter.characters[1].conditions.is_empty());
+        assert_eq!(encounter.resolve_concentration_check(check, true), Err(String::from("Concentration check not found")));
+
+        encounter.undo().unwrap();
+        assert_eq!(encounter.concentration_checks.len(), 1);
+        assert_eq!(encounter.characters[1].conditions.len(), 1);
+
+        let log: Vec<String> = encounter.log().iter().map(|entry| entry.event.describe()).collect();
+        assert!(log.contains(&String::from("Gandalf kept concentrating on Hold Person with a DC 12 save")));
+        assert!(log.contains(&String::from("Gandalf lost concentration on Hold Person after failing a DC 12 save")));
+        assert!(log.contains(&String::from("Orc is no longer paralyzed")));
+    }
+
+    #[test]
+    fn falling_unconscious_ends_concentration() {
+        let mut encounter = Encounter::new("Lord of the Rings");
+        let gandalf = Character::new("Gandalf", 20, 15);
+        let orc = Character::new("Orc", 15, 12);
+        encounter.add_character(gandalf.clone());
+        encounter.add_character(orc.clone());
+        encounter.update_character(character::UpdateCharacterCommand::UpdateConcentration { id: gandalf.uuid(), spell: Some(String::from("Hold Person")) }).unwrap();
+        let paralyzed = character::UpdateCharacterCommand::AddCondition {
+            id: orc.uuid(),
+            kind: character::ConditionKind::Paralyzed,
+            duration: character::ConditionDuration::default(),
+            source: Some(gandalf.uuid()),
+            concentration: true,
+        };
+        encounter.update_character(paralyzed).unwrap();
+
+        let damage = character::UpdateCharacterCommand::Damage { id: gandalf.uuid(), hp: 25, damage_type: None, critical: false };
+        let CharacterCommandResponse::UpdatedCharacter { concentration_checks, .. } = encounter.update_character(damage).unwrap() else {
+            panic!("expected a single character to be updated");
+        };
+        assert!(concentration_checks.is_empty(), "there is nothing left to check");
+        assert!(encounter.characters[1].conditions.is_empty());
+        let log: Vec<String> = encounter.log().iter().map(|entry| entry.event.describe()).collect();
+        assert!(log.contains(&String::from("Gandalf is no longer concentrating on Hold Person")));
+    }
+
+    #[test]
+    fn invalid_stats_come_back_as_messages() {
+        let mut encounter = Encounter::new("Lord of the Rings");
+        let gandalf = Character::new("Gandalf", 60, 15);
+        encounter.add_character(gandalf.clone());
+
+        let cmd = character::UpdateCharacterCommand::UpdateAbilityScore { id: gandalf.uuid(), ability: Ability::Constitution, score: 0 };
+        let CharacterCommandResponse::UpdatedCharacter { character, messages, .. } = encounter.update_character(cmd).unwrap() else {
+            panic!("expected a single character to be updated");
+        };
+        assert_eq!(character.ability_scores.constitution, 10);
+        assert_eq!(messages.ability_scores, vec![FrontendMessage::error("Constitution must be between 1 and 30")]);
+
+        let cmd = character::UpdateCharacterCommand::UpdateAbilityScore { id: gandalf.uuid(), ability: Ability::Constitution, score: 16 };
+        encounter.update_character(cmd).unwrap();
+        let cmd = character::UpdateCharacterCommand::UpdateSaveProficiencies { id: gandalf.uuid(), abilities: vec![Ability::Constitution] };
+        encounter.update_character(cmd).unwrap();
+        encounter.update_character(character::UpdateCharacterCommand::UpdateConcentration { id: gandalf.uuid(), spell: Some(String::from("Fly")) }).unwrap();
+
+        let damage = character::UpdateCharacterCommand::Damage { id: gandalf.uuid(), hp: 8, damage_type: None, critical: false };
+        let CharacterCommandResponse::UpdatedCharacter { concentration_checks, .. } = encounter.update_character(damage).unwrap() else {
+            panic!("expected a single character to be updated");
+        };
+        assert_eq!(concentration_checks[0].modifier, 5);
+    }
 }
@@ -0,0 +1,118 @@
+use uuid::Uuid;
+
+use crate::encounters::Character;
+use crate::encounters::character::{ConcentrationCheck, ExpiredCondition};
+use crate::encounters::initiative::{CombatantGroup, InitiativeEntry, PassedTurn, ReadiedAction, SkippedTurn};
+
+pub const HISTORY_LIMIT: usize = 100;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TurnState {
+    pub active_character: Option<Uuid>,
+    pub last_active_character: Option<Uuid>,
+    pub round: u32,
+    pub turn: u32,
+    pub acted: Vec<Uuid>,
+    pub delayed: Vec<Uuid>,
+    pub readied: Vec<ReadiedAction>,
+    pub surprise: bool,
+    pub surprise_round: bool,
+    pub skipped: Vec<SkippedTurn>,
+    pub expired: Vec<ExpiredCondition>,
+    pub passed_turns: Vec<PassedTurn>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RosterState {
+    pub characters: Vec<Character>,
+    pub groups: Vec<CombatantGroup>,
+    pub entries: Vec<InitiativeEntry>,
+    pub concentration_checks: Vec<ConcentrationCheck>,
+    pub manual_order: bool,
+    pub turn: TurnState,
+}
+
+// Undo and redo both put back a piece of state
+#[derive(Clone, Debug, PartialEq)]
+pub enum EncounterChange {
+    Character(Character),
+    Roster(RosterState),
+    Turn(TurnState),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct EncounterHistory {
+    undo: Vec<EncounterChange>,
+    redo: Vec<EncounterChange>,
+}
+
+impl EncounterHistory {
+    pub fn record(&mut self, inverse: EncounterChange) {
+        self.undo.push(inverse);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn pop_undo(&mut self) -> Option<EncounterChange> {
+        self.undo.pop()
+    }
+
+    pub fn pop_redo(&mut self) -> Option<EncounterChange> {
+        self.redo.pop()
+    }
+
+    pub fn push_undo(&mut self, change: EncounterChange) {
+        self.undo.push(change);
+    }
+
+    pub fn push_redo(&mut self, change: EncounterChange) {
+        self.redo.push(change);
+    }
+}
+
+// History only lives for the session, so it never makes two encounters different
+impl PartialEq for EncounterHistory {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(id: Uuid) -> EncounterChange {
+        EncounterChange::Turn(TurnState { active_character: Some(id), last_active_character: None, round: 1, turn: 0, acted: Vec::new(), delayed: Vec::new(), readied: Vec::new(), surprise: false, surprise_round: false, skipped: Vec::new(), expired: Vec::new(), passed_turns: Vec::new() })
+    }
+
+    #[test]
+    fn test_record_clears_redo() {
+        let mut history = EncounterHistory::default();
+        let latest = turn(Uuid::new_v4());
+        history.record(turn(Uuid::new_v4()));
+        history.push_redo(turn(Uuid::new_v4()));
+
+        history.record(latest.clone());
+        assert_eq!(history.pop_redo(), None);
+        assert_eq!(history.pop_undo(), Some(latest));
+    }
+
+    #[test]
+    fn test_record_is_limited() {
+        let mut history = EncounterHistory::default();
+        let oldest = turn(Uuid::new_v4());
+        history.record(oldest.clone());
+        for _ in 0..HISTORY_LIMIT {
+            history.record(turn(Uuid::new_v4()));
+        }
+
+        let mut remaining = 0;
+        while let Some(change) = history.pop_undo() {
+            assert_ne!(change, oldest);
+            remaining += 1;
+        }
+        assert_eq!(remaining, HISTORY_LIMIT);
+    }
+}
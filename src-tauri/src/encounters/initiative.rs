@@ -0,0 +1,167 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+use uuid::Uuid;
+
+use crate::encounters::Character;
+use crate::encounters::character::ConditionChange;
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum TieBreakPolicy {
+    PlayersFirst,
+    // The rules break ties on the Dexterity score, so this is what new encounters start with
+    #[default]
+    Dexterity,
+    TieBreakerRoll,
+    // Tied combatants keep whatever order they are already in
+    Manual,
+}
+
+impl TieBreakPolicy {
+    pub fn compare(&self, a: &Character, b: &Character) -> Ordering {
+        let ordering = a.cmp(b);
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        let ordering = match self {
+            TieBreakPolicy::PlayersFirst => b.player.cmp(&a.player),
+            TieBreakPolicy::Dexterity => b.ability_scores.dexterity.cmp(&a.ability_scores.dexterity),
+            TieBreakPolicy::TieBreakerRoll => b.tie_breaker.cmp(&a.tie_breaker),
+            TieBreakPolicy::Manual => return Ordering::Equal,
+        };
+        // Whatever is still tied gets a fixed order, so it cannot depend on when characters were added
+        ordering
+            .then_with(|| b.tie_breaker.cmp(&a.tie_breaker))
+            .then_with(|| a.uuid().cmp(&b.uuid()))
+    }
+
+    // `Manual` relies on the sort being stable
+    pub fn sort(&self, characters: &mut [Character]) {
+        characters.sort_by(|a, b| self.compare(a, b));
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadiedAction {
+    pub id: Uuid,
+    pub trigger: String,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SkipReason {
+    Surprised,
+    Down,
+    SkipTurn,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedTurn {
+    pub id: Uuid,
+    pub name: String,
+    pub reason: SkipReason,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PassedTurn {
+    pub id: Uuid,
+    pub round: u32,
+    pub conditions: Vec<ConditionChange>,
+}
+
+// The first member holds the slot
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CombatantGroup {
+    pub id: Uuid,
+    pub name: String,
+    pub members: Vec<Uuid>,
+}
+
+impl CombatantGroup {
+    pub fn new<T: Into<String>>(name: T, members: Vec<Uuid>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: name.into(),
+            members,
+        }
+    }
+
+    pub fn leader(&self) -> Uuid {
+        self.members[0]
+    }
+
+    pub fn contains(&self, id: Uuid) -> bool {
+        self.members.contains(&id)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct InitiativeEntry {
+    pub id: Uuid,
+    pub name: String,
+    pub initiative: i32,
+    pub character: Option<Uuid>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tied(name: &str) -> Character {
+        Character::new(name, 10, 15)
+    }
+
+    #[test]
+    fn test_tie_break_policies() {
+        let mut monster = tied("Monster");
+        monster.ability_scores.dexterity = 16;
+        monster.tie_breaker = Some(3);
+        let mut player = tied("Player");
+        player.player = true;
+        player.ability_scores.dexterity = 12;
+        player.tie_breaker = Some(18);
+        let fast = Character::new("Fast", 10, 20);
+
+        let names = |policy: TieBreakPolicy| {
+            let mut characters = vec![monster.clone(), player.clone(), fast.clone()];
+            policy.sort(&mut characters);
+            characters.into_iter().map(|c| c.name).collect::<Vec<_>>()
+        };
+        assert_eq!(names(TieBreakPolicy::PlayersFirst), vec!["Fast", "Player", "Monster"]);
+        assert_eq!(names(TieBreakPolicy::Dexterity), vec!["Fast", "Monster", "Player"]);
+        assert_eq!(names(TieBreakPolicy::TieBreakerRoll), vec!["Fast", "Player", "Monster"]);
+        assert_eq!(names(TieBreakPolicy::Manual), vec!["Fast", "Monster", "Player"]);
+    }
+
+    #[test]
+    fn test_remaining_ties_do_not_depend_on_order() {
+        let mut rolled = tied("Rolled");
+        rolled.tie_breaker = Some(12);
+        let (a, b) = (tied("A"), tied("B"));
+        for policy in [TieBreakPolicy::PlayersFirst, TieBreakPolicy::Dexterity, TieBreakPolicy::TieBreakerRoll] {
+            let mut forward = vec![a.clone(), rolled.clone(), b.clone()];
+            let mut backward = vec![b.clone(), rolled.clone(), a.clone()];
+            policy.sort(&mut forward);
+            policy.sort(&mut backward);
+            assert_eq!(forward, backward);
+            assert_eq!(forward[0].name, "Rolled");
+        }
+    }
+
+    #[test]
+    fn test_manual_keeps_existing_order() {
+        let mut characters = vec![tied("A"), tied("B"), tied("C")];
+        for _ in 0..3 {
+            TieBreakPolicy::Manual.sort(&mut characters);
+            assert_eq!(characters.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["A", "B", "C"]);
+        }
+    }
+}
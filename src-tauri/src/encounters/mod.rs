@@ -2,5 +2,8 @@ pub use crate::encounters::character::Character;
 pub use crate::encounters::encounter::{Encounter, EncounterCollection};
 
 pub mod character;
+pub mod combat_log;
 pub mod encounter;
 pub mod commands;
+pub mod history;
+pub mod initiative;
@@ -4,24 +4,26 @@
 extern crate core;
 
 mod commands;
+mod dice;
 mod encounters;
 mod services;
 
 use specta::collect_types;
 use tauri_specta::ts;
-use tauri::{generate_context, Manager};
+use tauri::{async_runtime, generate_context, Manager};
 
 use crate::{
-    commands::{encounter, query_file_system, update_encounter_character},
+    commands::{encounter, encounter_checkpoint, encounter_conflict, encounter_log, encounter_startup_events, query_file_system, roll_dice, update_encounter_character},
     services::file_system_connection,
 };
 use crate::commands::new_character;
 use crate::encounters::encounter::EncounterManager;
 use crate::services::files::notify_file_change;
+use crate::services::storage::{notify_encounter_change, watch_encounter_files, Autosave, EncounterFileEvent, EncounterStore};
 
 fn export_bindings() {
     ts::export(collect_types![query_file_system, notify_file_change], "../src/fileBindings.ts").unwrap();
-    ts::export(collect_types![encounter, update_encounter_character, new_character], "../src/encounterBindings.ts").unwrap();
+    ts::export(collect_types![encounter, update_encounter_character, encounter_conflict, encounter_startup_events, encounter_checkpoint, encounter_log, new_character, roll_dice, notify_encounter_change], "../src/encounterBindings.ts").unwrap();
 }
 
 fn main() {
@@ -30,51 +32,47 @@ fn main() {
 
     tauri::Builder::default()
         .setup(|app| {
-            let lotr = load_of_rings_mock();
-            let hp = harry_potter_mock();
-            let mut encounter_collection = encounters::EncounterCollection::new();
-            encounter_collection.add_encounter(lotr);
-            encounter_collection.add_encounter(hp);
-
             let arc_data = file_system_connection::start(app.handle())?;
+            let store = async_runtime::block_on(async {
+                EncounterStore::new(arc_data.lock().await.file_query.root())
+            })?;
+
+            let autosave = Autosave::new(store.clone());
+
+            let (mut encounter_collection, skipped) = store.load_collection()?;
+            // The webview is not listening yet, so the frontend asks for these once it is ready
+            if !skipped.is_empty() {
+                autosave.report(EncounterFileEvent::Skipped(skipped));
+            }
+            // Whatever could not be recovered stays in the journal, so the app can still start
+            if let Err(error) = autosave.recover(&mut encounter_collection) {
+                autosave.report(EncounterFileEvent::SaveFailed { error });
+            }
+
+            let encounter_manager = EncounterManager::from(encounter_collection);
+            autosave.start(encounter_manager.clone(), app.handle());
+            watch_encounter_files(arc_data.clone(), encounter_manager.clone(), autosave.clone());
+
             app.manage(arc_data);
-            app.manage(EncounterManager::from(encounter_collection));
+            app.manage(autosave);
+            app.manage(encounter_manager);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             query_file_system,
             encounter,
             update_encounter_character,
+            encounter_conflict,
+            encounter_startup_events,
+            encounter_checkpoint,
+            encounter_log,
+            roll_dice,
             new_character
         ])
         .run(generate_context!())
         .expect("error while running tauri application");
 }
 
-fn load_of_rings_mock() -> encounters::Encounter {
-    let frodo = encounters::Character::new("Frodo", 8, 18);
-    let sam = encounters::Character::new("Sam", 6, 19);
-    let pippin = encounters::Character::new("Pippin", 4, 5);
-    let merry = encounters::Character::new("Merry", 7, 3);
-    let mut encounter = encounters::Encounter::new("Lord of the Rings");
-    encounter.add_character(frodo);
-    encounter.add_character(sam);
-    encounter.add_character(pippin);
-    encounter.add_character(merry);
-    encounter
-}
-
-fn harry_potter_mock() -> encounters::Encounter {
-    let harry = encounters::Character::new("Harry", 4, 18);
-    let ron = encounters::Character::new("Ron", 6, 3);
-    let hermione = encounters::Character::new("Hermione", 4, 14);
-    let mut encounter = encounters::Encounter::new("Harry Potter");
-    encounter.add_character(harry);
-    encounter.add_character(ron);
-    encounter.add_character(hermione);
-    encounter
-}
-
 #[test]
 fn test_export_bindings() {
     export_bindings();
@@ -4,7 +4,7 @@ use notify::RecursiveMode;
 use tauri::{api::path::document_dir, async_runtime, Manager, Runtime, State, Wry};
 use tokio::sync::{Mutex, MutexGuard};
 
-use crate::services::files::{FileData, file_structure::RootDirectory, FileChangeEvent, FileWatcher};
+use crate::services::files::{file_structure::RootDirectory, FileWatcher, RenameTracker};
 
 pub struct FileSystemConnection<R: Runtime> {
     pub app_handle: tauri::AppHandle<R>,
@@ -54,37 +54,14 @@ impl ArcFileSystemConnection {
         async_runtime::spawn(async move {
             let mut receiver = { (cloned_self.lock().await).file_watcher.sender.subscribe() };
 
-            let mut last_event: Option<FileChangeEvent> = None;
+            let mut renames = RenameTracker::default();
             loop {
-                let event = match receiver
-                    .recv()
-                    .await
-                    .expect("Something has happend to the connector")
-                {
-                    FileChangeEvent::Create(file_data) => Some(FileChangeEvent::Create(file_data)),
-                    FileChangeEvent::Delete(file_data) => Some(FileChangeEvent::Delete(file_data)),
-                    FileChangeEvent::Modify(file_data) => Some(FileChangeEvent::Modify(file_data)),
-                    FileChangeEvent::RenameAny { path } => {
-                        if let Some(FileChangeEvent::RenameAny { path: from }) = last_event {
-                            last_event = None;
-                            Some(FileChangeEvent::Rename {
-                                from: from.clone(),
-                                to: path.clone(),
-                                data: FileData::from(path),
-                            })
-                        } else {
-                            last_event = Some(FileChangeEvent::RenameAny { path });
-                            None
-                        }
-                    }
-                    FileChangeEvent::Rename { from, to, data } => {
-                        Some(FileChangeEvent::Rename { from, to, data })
-                    }
-                    FileChangeEvent::Ignore => {
-                        last_event = None;
-                        None
-                    }
-                };
+                let event = renames.track(
+                    receiver
+                        .recv()
+                        .await
+                        .expect("Something has happend to the connector"),
+                );
 
                 if let Some(event) = event {
                     let app_handle = cloned_self.lock().await.app_handle.clone();
@@ -73,6 +73,10 @@ impl RootDirectory {
         })
     }
 
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
     pub fn query_root(&self) -> Result<QueryCommandResponse, String> {
         if !self.root.exists() {
             return Err("Root Directory does not exist".to_string());
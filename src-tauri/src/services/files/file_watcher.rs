@@ -36,7 +36,7 @@ impl FileWatcher {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
 #[serde(rename_all = "camelCase")]
 pub enum FileChangeEvent {
     Create(FileData),
@@ -108,5 +108,61 @@ impl From<&notify::Event> for FileChangeEvent {
     }
 }
 
+#[derive(Debug, Default)]
+pub struct RenameTracker {
+    last_event: Option<FileChangeEvent>,
+}
+
+impl RenameTracker {
+    pub fn track(&mut self, event: FileChangeEvent) -> Option<FileChangeEvent> {
+        match event {
+            FileChangeEvent::RenameAny { path } => {
+                if let Some(FileChangeEvent::RenameAny { path: from }) = self.last_event.take() {
+                    Some(FileChangeEvent::Rename {
+                        from,
+                        to: path.clone(),
+                        data: FileData::from(path),
+                    })
+                } else {
+                    self.last_event = Some(FileChangeEvent::RenameAny { path });
+                    None
+                }
+            }
+            FileChangeEvent::Ignore => {
+                self.last_event = None;
+                None
+            }
+            event => Some(event),
+        }
+    }
+}
+
 #[specta::specta]
-pub fn notify_file_change(_event: FileChangeEvent) {}
\ No newline at end of file
+pub fn notify_file_change(_event: FileChangeEvent) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rename_tracker_joins_rename_halves() {
+        let mut renames = RenameTracker::default();
+        let from = PathBuf::from("/root/fileA");
+        let to = PathBuf::from("/root/fileB");
+
+        assert_eq!(renames.track(FileChangeEvent::RenameAny { path: from.clone() }), None);
+        let event = renames.track(FileChangeEvent::RenameAny { path: to.clone() });
+        assert_eq!(event, Some(FileChangeEvent::Rename { from, to: to.clone(), data: FileData::from(&to) }));
+    }
+
+    #[test]
+    fn test_rename_tracker_resets_on_ignore() {
+        let mut renames = RenameTracker::default();
+        let data = FileData::from(PathBuf::from("/root/fileA"));
+
+        assert_eq!(renames.track(FileChangeEvent::RenameAny { path: data.path.clone() }), None);
+        assert_eq!(renames.track(FileChangeEvent::Ignore), None);
+        assert_eq!(renames.track(FileChangeEvent::RenameAny { path: data.path.clone() }), None);
+        assert_eq!(renames.track(FileChangeEvent::Modify(data.clone())), Some(FileChangeEvent::Modify(data)));
+    }
+}
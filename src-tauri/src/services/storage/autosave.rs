@@ -0,0 +1,316 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use tauri::{async_runtime, AppHandle, State};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::encounters::{Encounter, EncounterCollection};
+use crate::encounters::encounter::EncounterManager;
+use crate::services::storage::{emit_encounter_event, ConflictResolution, EncounterConflict, EncounterFileEvent, EncounterStore, Journal, JournalEntry};
+
+pub const AUTOSAVE_DEBOUNCE: Duration = Duration::from_secs(2);
+
+pub type AutosaveState<'a> = State<'a, Autosave>;
+
+#[derive(Clone)]
+pub struct Autosave {
+    store: EncounterStore,
+    journal: Journal,
+    pending: Arc<Notify>,
+    conflicts: Arc<Mutex<HashMap<Uuid, EncounterConflict>>>,
+    startup_events: Arc<Mutex<Vec<EncounterFileEvent>>>,
+}
+
+impl Autosave {
+    pub fn new(store: EncounterStore) -> Self {
+        let journal = Journal::new(store.directory());
+        Self {
+            store,
+            journal,
+            pending: Arc::new(Notify::new()),
+            conflicts: Arc::new(Mutex::new(HashMap::new())),
+            startup_events: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub fn store(&self) -> &EncounterStore {
+        &self.store
+    }
+
+    // Call with the `EncounterManager` lock held, so a flush never drops an unwritten entry
+    pub fn record(&self, encounter: &Encounter) -> Result<(), String> {
+        self.journal.append(&JournalEntry::Updated(Box::new(encounter.clone())))?;
+        self.pending.notify_one();
+        Ok(())
+    }
+
+    pub fn record_deletion(&self, id: Uuid) -> Result<(), String> {
+        self.journal.append(&JournalEntry::Deleted(id))?;
+        self.pending.notify_one();
+        Ok(())
+    }
+
+    pub fn recover(&self, collection: &mut EncounterCollection) -> Result<(), String> {
+        if let Some(error) = self.journal.repair()? {
+            self.report(EncounterFileEvent::JournalCorrupted { path: self.journal.corrupt_path().display().to_string(), error });
+        }
+        let entries = self.journal.read()?;
+        if entries.is_empty() {
+            return Ok(());
+        }
+        entries.into_iter().for_each(|entry| entry.apply(collection));
+        self.flush(collection).map(|_| ())
+    }
+
+    // Returns the encounters whose file someone else changed in the meantime
+    pub fn flush(&self, collection: &EncounterCollection) -> Result<Vec<EncounterConflict>, String> {
+        let changed: HashSet<Uuid> = self.unsaved_encounters()?;
+
+        let mut new_conflicts = Vec::new();
+        for id in changed {
+            if self.has_conflict(id) {
+                continue;
+            }
+            match collection.find_encounter(id) {
+                Some(encounter) => match self.store.load_external_changes(id)? {
+                    Some(theirs) => new_conflicts.push(self.add_conflict(encounter, &theirs)),
+                    None => self.store.save(encounter)?,
+                },
+                None => self.store.delete(id)?,
+            }
+        }
+
+        // Unsaved changes stay in the journal until their conflict is resolved
+        self.journal.retain(|entry| self.has_conflict(entry.encounter_id()))?;
+        Ok(new_conflicts)
+    }
+
+    pub fn has_unsaved_changes(&self, id: Uuid) -> Result<bool, String> {
+        Ok(self.unsaved_encounters()?.contains(&id))
+    }
+
+    pub fn check_external(&self, collection: &EncounterCollection, theirs: &Encounter) -> Result<Option<EncounterConflict>, String> {
+        let mine = match collection.find_encounter(theirs.uuid()) {
+            Some(mine) if mine != theirs => mine,
+            _ => return Ok(None),
+        };
+        if !self.has_conflict(mine.uuid()) && !self.has_unsaved_changes(mine.uuid())? {
+            return Ok(None);
+        }
+        Ok(Some(self.add_conflict(mine, theirs)))
+    }
+
+    pub fn report(&self, event: EncounterFileEvent) {
+        self.startup_event_list().push(event);
+    }
+
+    pub fn startup_events(&self) -> Vec<EncounterFileEvent> {
+        self.startup_event_list().clone()
+    }
+
+    pub fn conflicts(&self) -> Vec<EncounterConflict> {
+        self.conflict_map().values().cloned().collect()
+    }
+
+    pub fn has_conflict(&self, id: Uuid) -> bool {
+        self.conflict_map().contains_key(&id)
+    }
+
+    pub fn resolve_conflict(&self, collection: &mut EncounterCollection, id: Uuid, resolution: &ConflictResolution) -> Result<Encounter, String> {
+        let conflict = self.conflict_map().get(&id).cloned().ok_or("Encounter has no conflict")?;
+        let mine = collection.find_encounter(id).unwrap_or(&conflict.mine);
+        let resolved = resolution.resolve(mine, &conflict.theirs)?;
+
+        self.conflict_map().remove(&id);
+        collection.add_encounter(resolved.clone());
+        self.record(&resolved)?;
+        Ok(resolved)
+    }
+
+    fn add_conflict(&self, mine: &Encounter, theirs: &Encounter) -> EncounterConflict {
+        let conflict = EncounterConflict::new(mine, theirs);
+        self.conflict_map().insert(conflict.id(), conflict.clone());
+        conflict
+    }
+
+    fn conflict_map(&self) -> MutexGuard<'_, HashMap<Uuid, EncounterConflict>> {
+        self.conflicts.lock().expect("encounter conflicts were poisoned")
+    }
+
+    fn startup_event_list(&self) -> MutexGuard<'_, Vec<EncounterFileEvent>> {
+        self.startup_events.lock().expect("encounter startup events were poisoned")
+    }
+
+    fn unsaved_encounters(&self) -> Result<HashSet<Uuid>, String> {
+        Ok(self.journal.read()?.iter().map(JournalEntry::encounter_id).collect())
+    }
+
+    pub fn start(&self, manager: EncounterManager, app_handle: AppHandle) {
+        let autosave = self.clone();
+        async_runtime::spawn(async move {
+            loop {
+                autosave.pending.notified().await;
+                // Keep waiting while changes keep coming in, so a burst of edits is written once
+                while tokio::time::timeout(AUTOSAVE_DEBOUNCE, autosave.pending.notified()).await.is_ok() {}
+
+                let collection = manager.lock().await;
+                let events = match autosave.flush(&collection) {
+                    Ok(conflicts) => conflicts.into_iter().map(|conflict| EncounterFileEvent::Conflicted(Box::new(conflict))).collect(),
+                    Err(error) => vec![EncounterFileEvent::SaveFailed { error }],
+                };
+                // Conflicts stay listed and unsaved changes stay journaled, so a missed event loses nothing
+                for event in events {
+                    let _ = emit_encounter_event(&app_handle, event);
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::encounters::Character;
+    use crate::services::storage::format::to_document;
+    use crate::services::storage::{CORRUPT_JOURNAL_FILE, JOURNAL_FILE};
+
+    #[test]
+    fn test_record_and_flush() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let autosave = Autosave::new(EncounterStore::new(tmp_dir.path()).unwrap());
+        let mut collection = EncounterCollection::new();
+        let mut encounter = Encounter::new("Lord of the Rings");
+        encounter.add_character(Character::new("Frodo", 8, 18));
+        collection.add_encounter(encounter.clone());
+
+        autosave.record(&encounter).unwrap();
+        let path = autosave.store.path_for(encounter.uuid());
+        assert!(!path.exists(), "recording only writes to the journal");
+
+        autosave.flush(&collection).unwrap();
+        assert_eq!(autosave.store.load(&path).unwrap(), encounter);
+        assert_eq!(autosave.journal.read().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_flush_deletes_removed_encounters() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let autosave = Autosave::new(EncounterStore::new(tmp_dir.path()).unwrap());
+        let mut collection = EncounterCollection::new();
+        let encounter = Encounter::new("Lord of the Rings");
+        collection.add_encounter(encounter.clone());
+        autosave.store.save(&encounter).unwrap();
+
+        collection.delete_encounter(encounter.uuid()).unwrap();
+        autosave.record_deletion(encounter.uuid()).unwrap();
+        let path = autosave.store.path_for(encounter.uuid());
+        assert!(path.exists(), "recording only writes to the journal");
+
+        autosave.flush(&collection).unwrap();
+        assert!(!path.exists());
+        assert_eq!(autosave.store.tracked(&path), None);
+        assert_eq!(autosave.journal.read().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_flush_keeps_conflicting_changes() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let autosave = Autosave::new(EncounterStore::new(tmp_dir.path()).unwrap());
+        let frodo = Character::new("Frodo", 8, 18);
+        let mut encounter = Encounter::new("Lord of the Rings");
+        encounter.add_character(frodo.clone());
+        autosave.store.save(&encounter).unwrap();
+
+        // Someone edits the file while we still have a change waiting to be written
+        let mut theirs = encounter.clone();
+        theirs.find_character_mut(frodo.uuid()).unwrap().set_name("Mr. Underhill").unwrap();
+        let path = autosave.store.path_for(encounter.uuid());
+        fs::write(&path, to_document(&theirs).unwrap()).unwrap();
+
+        encounter.find_character_mut(frodo.uuid()).unwrap().damage(3, None);
+        let mut collection = EncounterCollection::new();
+        collection.add_encounter(encounter.clone());
+        autosave.record(&encounter).unwrap();
+
+        let conflicts = autosave.flush(&collection).unwrap();
+        assert_eq!(conflicts, vec![EncounterConflict::new(&encounter, &theirs)]);
+        assert_eq!(autosave.store.load(&path).unwrap(), theirs);
+        assert!(autosave.has_unsaved_changes(encounter.uuid()).unwrap());
+
+        let resolved = autosave.resolve_conflict(&mut collection, encounter.uuid(), &ConflictResolution::KeepMine).unwrap();
+        assert_eq!(resolved, encounter);
+        assert!(!autosave.has_conflict(encounter.uuid()));
+
+        assert_eq!(autosave.flush(&collection).unwrap(), vec![]);
+        assert_eq!(autosave.store.load(&path).unwrap(), encounter);
+    }
+
+    #[test]
+    fn test_check_external() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let autosave = Autosave::new(EncounterStore::new(tmp_dir.path()).unwrap());
+        let mut encounter = Encounter::new("Lord of the Rings");
+        let mut collection = EncounterCollection::new();
+        collection.add_encounter(encounter.clone());
+
+        let mut theirs = encounter.clone();
+        theirs.add_character(Character::new("Pippin", 4, 5));
+
+        // Without unsaved changes the file simply wins
+        assert_eq!(autosave.check_external(&collection, &theirs).unwrap(), None);
+
+        encounter.add_character(Character::new("Sam", 6, 19));
+        collection.add_encounter(encounter.clone());
+        autosave.record(&encounter).unwrap();
+        let conflict = EncounterConflict::new(&encounter, &theirs);
+        assert_eq!(autosave.check_external(&collection, &theirs).unwrap(), Some(conflict.clone()));
+        assert_eq!(autosave.conflicts(), vec![conflict]);
+    }
+
+    #[test]
+    fn test_recover_replays_journal() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let store = EncounterStore::new(tmp_dir.path()).unwrap();
+        let mut encounter = Encounter::new("Lord of the Rings");
+        encounter.add_character(Character::new("Frodo", 8, 18));
+        encounter.add_character(Character::new("Sam", 6, 19));
+        store.save(&encounter).unwrap();
+
+        // Changes that were journaled but never folded into the saved file before a crash
+        let autosave = Autosave::new(store.clone());
+        encounter.start().unwrap();
+        autosave.record(&encounter).unwrap();
+        encounter.next().unwrap();
+        autosave.record(&encounter).unwrap();
+
+        let autosave = Autosave::new(store.clone());
+        let (mut collection, _) = store.load_collection().unwrap();
+        autosave.recover(&mut collection).unwrap();
+
+        assert_eq!(collection.find_encounter(encounter.uuid()), Some(&encounter));
+        assert_eq!(store.load(&store.path_for(encounter.uuid())).unwrap(), encounter);
+        assert_eq!(autosave.journal.read().unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_recover_sets_a_corrupt_journal_aside() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let store = EncounterStore::new(tmp_dir.path()).unwrap();
+        let autosave = Autosave::new(store.clone());
+        let encounter = Encounter::new("Lord of the Rings");
+        fs::write(store.directory().join(JOURNAL_FILE), "not an entry\n").unwrap();
+        autosave.record(&encounter).unwrap();
+
+        let mut collection = EncounterCollection::new();
+        autosave.recover(&mut collection).unwrap();
+
+        assert_eq!(collection.find_encounter(encounter.uuid()), Some(&encounter));
+        assert!(store.directory().join(CORRUPT_JOURNAL_FILE).exists());
+        let events = autosave.startup_events();
+        assert!(matches!(&events[..], [EncounterFileEvent::JournalCorrupted { error, .. }] if error.starts_with("Journal entry on line 1")));
+    }
+}
@@ -0,0 +1,205 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use specta::Type;
+use uuid::Uuid;
+
+use crate::encounters::{Encounter, EncounterCollection};
+use crate::encounters::combat_log::CombatEvent;
+use crate::services::unix_timestamp;
+use crate::services::storage::{write_atomically, Autosave};
+use crate::services::storage::format::{document_version, upgrade_encounter, FORMAT_VERSION};
+
+pub const CHECKPOINT_EXTENSION: &str = "checkpoints";
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    pub id: Uuid,
+    pub label: String,
+    pub created_at: u32,
+    pub encounter: Encounter,
+}
+
+#[derive(Serialize)]
+struct CheckpointDocument<'a> {
+    version: u64,
+    checkpoints: &'a [Checkpoint],
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckpointCommands {
+    Create { id: Uuid, label: String },
+    List { id: Uuid },
+    Restore { id: Uuid, checkpoint_id: Uuid },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckpointCommandResponse {
+    Created(Checkpoint),
+    CheckpointList(Vec<Checkpoint>),
+    Restored(Encounter),
+}
+
+#[derive(Clone, Debug)]
+pub struct Checkpoints {
+    directory: PathBuf,
+}
+
+impl Checkpoints {
+    pub fn new(directory: &Path) -> Self {
+        Self {
+            directory: directory.to_path_buf(),
+        }
+    }
+
+    pub fn path_for(&self, encounter_id: Uuid) -> PathBuf {
+        self.directory.join(encounter_id.to_string()).with_extension(CHECKPOINT_EXTENSION)
+    }
+
+    pub fn list(&self, encounter_id: Uuid) -> Result<Vec<Checkpoint>, String> {
+        let path = self.path_for(encounter_id);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        parse_checkpoints(&contents).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    }
+
+    pub fn create<T: Into<String>>(&self, encounter: &Encounter, label: T) -> Result<Checkpoint, String> {
+        let label = label.into();
+        if label.trim().is_empty() {
+            return Err(String::from("Checkpoint label cannot be empty"));
+        }
+
+        let checkpoint = Checkpoint {
+            id: Uuid::new_v4(),
+            label,
+            created_at: unix_timestamp(),
+            encounter: encounter.clone(),
+        };
+        let mut checkpoints = self.list(encounter.uuid())?;
+        checkpoints.push(checkpoint.clone());
+
+        let document = CheckpointDocument { version: FORMAT_VERSION, checkpoints: &checkpoints };
+        let contents = serde_yaml::to_string(&document).map_err(|e| e.to_string())?;
+        write_atomically(&self.path_for(encounter.uuid()), &contents)?;
+        Ok(checkpoint)
+    }
+
+    pub fn delete(&self, encounter_id: Uuid) -> Result<(), String> {
+        let path = self.path_for(encounter_id);
+        if !path.exists() {
+            return Ok(());
+        }
+        fs::remove_file(&path).map_err(|e| e.to_string())
+    }
+
+    // Callers must hold the `EncounterManager` lock
+    pub fn restore(&self, collection: &mut EncounterCollection, autosave: &Autosave, encounter_id: Uuid, checkpoint_id: Uuid) -> Result<Encounter, String> {
+        let current = collection.find_encounter(encounter_id).ok_or("Encounter not found")?;
+        let checkpoint = self.list(encounter_id)?
+            .into_iter()
+            .find(|checkpoint| checkpoint.id == checkpoint_id)
+            .ok_or("Checkpoint not found")?;
+
+        let mut restored = checkpoint.encounter;
+        restored.continue_log_from(current);
+        restored.record_event(CombatEvent::CheckpointRestored { label: checkpoint.label });
+
+        collection.add_encounter(restored.clone());
+        autosave.record(&restored)?;
+        Ok(restored)
+    }
+}
+
+fn parse_checkpoints(contents: &str) -> Result<Vec<Checkpoint>, String> {
+    let mut document: Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+    let version = document_version(&document)?;
+    let checkpoints = document
+        .get_mut("checkpoints")
+        .and_then(Value::as_sequence_mut)
+        .ok_or("Checkpoint file has no checkpoints")?;
+
+    // Checkpoints hold whole encounters, so they go through the same migrations as encounter files
+    for checkpoint in checkpoints.iter_mut() {
+        let encounter = checkpoint.get("encounter").cloned().ok_or("Checkpoint has no encounter")?;
+        let encounter = upgrade_encounter(version, encounter)?;
+        checkpoint.as_mapping_mut()
+            .ok_or("Checkpoint is not a mapping")?
+            .insert(Value::from("encounter"), encounter);
+    }
+    serde_yaml::from_value(Value::Sequence(checkpoints.clone())).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encounters::Character;
+    use crate::services::storage::EncounterStore;
+
+    #[test]
+    fn test_create_and_list() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let checkpoints = Checkpoints::new(tmp_dir.path());
+        let mut encounter = Encounter::new("Lord of the Rings");
+        encounter.add_character(Character::new("Frodo", 8, 18));
+
+        assert_eq!(checkpoints.list(encounter.uuid()).unwrap(), vec![]);
+
+        let first = checkpoints.create(&encounter, "Start of round 1").unwrap();
+        encounter.start().unwrap();
+        let second = checkpoints.create(&encounter, "Start of round 2").unwrap();
+
+        assert_eq!(checkpoints.list(encounter.uuid()).unwrap(), vec![first.clone(), second]);
+        assert_eq!(first.label, "Start of round 1");
+        assert_eq!(first.encounter.get_active_character_id(), None);
+
+        let result = checkpoints.create(&encounter, " ");
+        assert_eq!(result.err(), Some(String::from("Checkpoint label cannot be empty")));
+    }
+
+    #[test]
+    fn test_restore() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let autosave = Autosave::new(EncounterStore::new(tmp_dir.path()).unwrap());
+        let checkpoints = Checkpoints::new(autosave.store().directory());
+        let frodo = Character::new("Frodo", 8, 18);
+        let mut encounter = Encounter::new("Lord of the Rings");
+        encounter.add_character(frodo.clone());
+        encounter.add_character(Character::new("Sam", 6, 19));
+        encounter.start().unwrap();
+        let checkpoint = checkpoints.create(&encounter, "Before the cave troll").unwrap();
+
+        encounter.next().unwrap();
+        encounter.find_character_mut(frodo.uuid()).unwrap().damage(5, None);
+        let mut collection = EncounterCollection::new();
+        collection.add_encounter(encounter.clone());
+
+        let restored = checkpoints.restore(&mut collection, &autosave, encounter.uuid(), checkpoint.id).unwrap();
+        assert_eq!(restored.get_characters(), checkpoint.encounter.get_characters());
+        assert_eq!(restored.get_active_character_id(), checkpoint.encounter.get_active_character_id());
+        assert_eq!(collection.find_encounter(encounter.uuid()), Some(&restored));
+
+        // The log keeps everything that happened before the restore
+        assert_eq!(restored.log().len(), encounter.log().len() + 1);
+        assert_eq!(restored.log().last().unwrap().event.describe(), "Restored checkpoint \"Before the cave troll\"");
+        assert!(autosave.has_unsaved_changes(encounter.uuid()).unwrap());
+
+        let result = checkpoints.restore(&mut collection, &autosave, encounter.uuid(), Uuid::new_v4());
+        assert_eq!(result.err(), Some(String::from("Checkpoint not found")));
+    }
+
+    #[test]
+    fn test_checkpoints_are_not_encounter_files() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let store = EncounterStore::new(tmp_dir.path()).unwrap();
+        let checkpoints = Checkpoints::new(store.directory());
+
+        assert!(!store.is_encounter_file(&checkpoints.path_for(Uuid::new_v4())));
+    }
+}
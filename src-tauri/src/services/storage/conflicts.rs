@@ -0,0 +1,253 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use uuid::Uuid;
+
+use crate::encounters::{Character, Encounter};
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldDiff {
+    pub field: String,
+    pub mine: String,
+    pub theirs: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CharacterChange {
+    OnlyMine,
+    OnlyTheirs,
+    Changed(Vec<FieldDiff>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterDiff {
+    pub id: Uuid,
+    pub name: String,
+    pub change: CharacterChange,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EncounterConflict {
+    pub mine: Encounter,
+    pub theirs: Encounter,
+    pub characters: Vec<CharacterDiff>,
+}
+
+impl EncounterConflict {
+    pub fn new(mine: &Encounter, theirs: &Encounter) -> Self {
+        Self {
+            mine: mine.clone(),
+            theirs: theirs.clone(),
+            characters: diff_characters(mine, theirs),
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.mine.uuid()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CharacterField {
+    pub character_id: Uuid,
+    pub field: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictResolution {
+    KeepMine,
+    KeepTheirs,
+    // Takes theirs for the listed fields and for characters only they have
+    Merge { take_theirs: Vec<CharacterField> },
+}
+
+impl ConflictResolution {
+    pub fn resolve(&self, mine: &Encounter, theirs: &Encounter) -> Result<Encounter, String> {
+        match self {
+            ConflictResolution::KeepMine => Ok(mine.clone()),
+            ConflictResolution::KeepTheirs => Ok(theirs.clone()),
+            ConflictResolution::Merge { take_theirs } => merge(mine, theirs, take_theirs),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictCommands {
+    ListConflicts,
+    Resolve { id: Uuid, resolution: ConflictResolution },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictCommandResponse {
+    ConflictList(Vec<EncounterConflict>),
+    Resolved(Box<Encounter>),
+}
+
+pub fn diff_characters(mine: &Encounter, theirs: &Encounter) -> Vec<CharacterDiff> {
+    let mut diffs: Vec<CharacterDiff> = mine.get_characters()
+        .iter()
+        .filter_map(|character| {
+            let change = match theirs.find_character(character.id()) {
+                Some(their_character) => {
+                    let fields = diff_fields(character, their_character);
+                    if fields.is_empty() {
+                        return None;
+                    }
+                    CharacterChange::Changed(fields)
+                }
+                None => CharacterChange::OnlyMine,
+            };
+            Some(CharacterDiff { id: character.uuid(), name: character.name.clone(), change })
+        })
+        .collect();
+
+    diffs.extend(theirs.get_characters()
+        .iter()
+        .filter(|character| mine.find_character(character.id()).is_none())
+        .map(|character| CharacterDiff {
+            id: character.uuid(),
+            name: character.name.clone(),
+            change: CharacterChange::OnlyTheirs,
+        }));
+    diffs
+}
+
+fn diff_fields(mine: &Character, theirs: &Character) -> Vec<FieldDiff> {
+    let mut diffs = Vec::new();
+    let mine = serde_json::to_value(mine).unwrap_or_default();
+    let theirs = serde_json::to_value(theirs).unwrap_or_default();
+    diff_values("", &mine, &theirs, &mut diffs);
+    diffs
+}
+
+fn diff_values(path: &str, mine: &Value, theirs: &Value, diffs: &mut Vec<FieldDiff>) {
+    match (mine, theirs) {
+        (Value::Object(mine), Value::Object(theirs)) => {
+            let keys: BTreeSet<&String> = mine.keys().chain(theirs.keys()).collect();
+            for key in keys {
+                let field = match path.is_empty() {
+                    true => key.clone(),
+                    false => format!("{}.{}", path, key),
+                };
+                let mine = mine.get(key).unwrap_or(&Value::Null);
+                let theirs = theirs.get(key).unwrap_or(&Value::Null);
+                diff_values(&field, mine, theirs, diffs);
+            }
+        }
+        (mine, theirs) if mine != theirs => diffs.push(FieldDiff {
+            field: path.to_string(),
+            mine: mine.to_string(),
+            theirs: theirs.to_string(),
+        }),
+        _ => {}
+    }
+}
+
+fn merge(mine: &Encounter, theirs: &Encounter, take_theirs: &[CharacterField]) -> Result<Encounter, String> {
+    let mut merged = mine.clone();
+    for choice in take_theirs {
+        let their_character = theirs.find_character(choice.character_id.to_string())
+            .ok_or(format!("Character with id {} not found", choice.character_id))?;
+        let character = merged.find_character_mut(choice.character_id)
+            .ok_or(format!("Character with id {} not found", choice.character_id))?;
+        *character = take_field(character, their_character, &choice.field)?;
+    }
+
+    for character in theirs.get_characters() {
+        if merged.find_character(character.id()).is_none() {
+            merged.add_character(character);
+        }
+    }
+    Ok(merged)
+}
+
+fn take_field(mine: &Character, theirs: &Character, field: &str) -> Result<Character, String> {
+    let pointer = format!("/{}", field.replace('.', "/"));
+    let mut merged = serde_json::to_value(mine).map_err(|e| e.to_string())?;
+    let theirs = serde_json::to_value(theirs).map_err(|e| e.to_string())?;
+
+    let value = theirs.pointer(&pointer).cloned().ok_or(format!("Unknown character field {}", field))?;
+    *merged.pointer_mut(&pointer).ok_or(format!("Unknown character field {}", field))? = value;
+    serde_json::from_value(merged).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Encounter, Encounter, Character) {
+        let frodo = Character::new("Frodo", 8, 18);
+        let mut mine = Encounter::new("Lord of the Rings");
+        mine.add_character(frodo.clone());
+        let theirs = mine.clone();
+        (mine, theirs, frodo)
+    }
+
+    #[test]
+    fn test_diff_changed_fields() {
+        let (mut mine, mut theirs, frodo) = setup();
+        mine.find_character_mut(frodo.uuid()).unwrap().damage(3, None);
+        theirs.find_character_mut(frodo.uuid()).unwrap().set_name("Mr. Underhill").unwrap();
+
+        let conflict = EncounterConflict::new(&mine, &theirs);
+        assert_eq!(conflict.characters, vec![CharacterDiff {
+            id: frodo.uuid(),
+            name: String::from("Frodo"),
+            change: CharacterChange::Changed(vec![
+                FieldDiff { field: String::from("hp.current"), mine: String::from("5"), theirs: String::from("8") },
+                FieldDiff { field: String::from("name"), mine: String::from("\"Frodo\""), theirs: String::from("\"Mr. Underhill\"") },
+            ]),
+        }]);
+    }
+
+    #[test]
+    fn test_diff_added_characters() {
+        let (mut mine, mut theirs, _) = setup();
+        let sam = Character::new("Sam", 6, 19);
+        let pippin = Character::new("Pippin", 4, 5);
+        mine.add_character(sam.clone());
+        theirs.add_character(pippin.clone());
+
+        assert_eq!(diff_characters(&mine, &theirs), vec![
+            CharacterDiff { id: sam.uuid(), name: String::from("Sam"), change: CharacterChange::OnlyMine },
+            CharacterDiff { id: pippin.uuid(), name: String::from("Pippin"), change: CharacterChange::OnlyTheirs },
+        ]);
+        assert_eq!(diff_characters(&mine, &mine), vec![]);
+    }
+
+    #[test]
+    fn test_resolutions() {
+        let (mut mine, mut theirs, frodo) = setup();
+        let pippin = Character::new("Pippin", 4, 5);
+        mine.find_character_mut(frodo.uuid()).unwrap().damage(3, None);
+        theirs.find_character_mut(frodo.uuid()).unwrap().set_name("Mr. Underhill").unwrap();
+        theirs.add_character(pippin.clone());
+
+        assert_eq!(ConflictResolution::KeepMine.resolve(&mine, &theirs).unwrap(), mine);
+        assert_eq!(ConflictResolution::KeepTheirs.resolve(&mine, &theirs).unwrap(), theirs);
+
+        let merge = ConflictResolution::Merge {
+            take_theirs: vec![CharacterField { character_id: frodo.uuid(), field: String::from("name") }],
+        };
+        let merged = merge.resolve(&mine, &theirs).unwrap();
+        let merged_frodo = merged.find_character(frodo.id()).unwrap();
+        assert_eq!(merged_frodo.name, "Mr. Underhill");
+        assert_eq!(merged_frodo.hp.current, 5);
+        assert!(merged.find_character(pippin.id()).is_some());
+
+        let merge = ConflictResolution::Merge {
+            take_theirs: vec![CharacterField { character_id: frodo.uuid(), field: String::from("hp.unknown") }],
+        };
+        assert_eq!(merge.resolve(&mine, &theirs).err(), Some(String::from("Unknown character field hp.unknown")));
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::fs::{create_dir_all, read_dir};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use uuid::Uuid;
+
+use crate::encounters::{Encounter, EncounterCollection};
+use crate::services::FrontendMessage;
+use crate::services::storage::format::{from_document, to_document};
+
+pub const ENCOUNTER_DIRECTORY: &str = "Encounters";
+pub const ENCOUNTER_EXTENSION: &str = "yaml";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrackedFile {
+    pub id: Uuid,
+    pub fingerprint: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct EncounterStore {
+    directory: PathBuf,
+    files: Arc<Mutex<HashMap<PathBuf, TrackedFile>>>,
+}
+
+impl EncounterStore {
+    pub fn new(root: &Path) -> Result<Self, String> {
+        let directory = root.join(ENCOUNTER_DIRECTORY);
+        if !directory.exists() {
+            create_dir_all(&directory).map_err(|e| e.to_string())?;
+        }
+        if !directory.is_dir() {
+            return Err(format!("{} is not a directory", directory.display()));
+        }
+        Ok(Self {
+            directory,
+            files: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn path_for(&self, id: Uuid) -> PathBuf {
+        self.directory.join(id.to_string()).with_extension(ENCOUNTER_EXTENSION)
+    }
+
+    pub fn is_encounter_file(&self, path: &Path) -> bool {
+        path.parent() == Some(self.directory.as_path())
+            && path.extension().is_some_and(|ext| ext == ENCOUNTER_EXTENSION)
+    }
+
+    pub fn save(&self, encounter: &Encounter) -> Result<(), String> {
+        let contents = to_document(encounter)?;
+        let path = self.file_for(encounter.uuid());
+        // Tracked before writing so the watcher never takes our own write for an outside change
+        let previous = self.tracked(&path);
+        self.track(&path, encounter.uuid(), &contents);
+        write_atomically(&path, &contents).inspect_err(|_| {
+            match previous {
+                Some(file) => self.tracked_files().insert(path.clone(), file),
+                None => self.untrack(&path),
+            };
+        })
+    }
+
+    pub fn delete(&self, id: Uuid) -> Result<(), String> {
+        let path = self.file_for(id);
+        self.untrack(&path);
+        if !path.exists() {
+            return Ok(());
+        }
+        fs::remove_file(&path).map_err(|e| e.to_string())
+    }
+
+    pub fn load(&self, path: &Path) -> Result<Encounter, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        self.parse(path, &contents)
+    }
+
+    // `None` when the file still holds what was last saved or loaded
+    pub fn load_if_changed(&self, path: &Path) -> Result<Option<Encounter>, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let unchanged = self.tracked(path)
+            .is_some_and(|file| file.fingerprint == fingerprint(&contents));
+        if unchanged {
+            return Ok(None);
+        }
+        self.parse(path, &contents).map(Some)
+    }
+
+    pub fn load_collection(&self) -> Result<(EncounterCollection, Vec<FrontendMessage>), String> {
+        let mut collection = EncounterCollection::new();
+        let mut skipped = Vec::new();
+        let entries = read_dir(&self.directory).map_err(|e| e.to_string())?;
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if !self.is_encounter_file(&path) {
+                continue;
+            }
+            match self.load(&path) {
+                Ok(encounter) => collection.add_encounter(encounter),
+                Err(e) => skipped.push(FrontendMessage::error(format!("Skipped encounter file: {}", e))),
+            }
+        }
+        Ok((collection, skipped))
+    }
+
+    pub fn load_external_changes(&self, id: Uuid) -> Result<Option<Encounter>, String> {
+        let path = self.file_for(id);
+        if !path.exists() || self.tracked(&path).is_none() {
+            return Ok(None);
+        }
+        self.load_if_changed(&path)
+    }
+
+    // May differ from `path_for` once a file was renamed by hand
+    pub fn file_for(&self, id: Uuid) -> PathBuf {
+        self.tracked_files()
+            .iter()
+            .find(|(_, file)| file.id == id)
+            .map(|(path, _)| path.clone())
+            .unwrap_or_else(|| self.path_for(id))
+    }
+
+    pub fn tracked(&self, path: &Path) -> Option<TrackedFile> {
+        self.tracked_files().get(path).copied()
+    }
+
+    pub fn untrack(&self, path: &Path) -> Option<TrackedFile> {
+        self.tracked_files().remove(path)
+    }
+
+    fn parse(&self, path: &Path, contents: &str) -> Result<Encounter, String> {
+        let encounter = from_document(contents)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        self.track(path, encounter.uuid(), contents);
+        Ok(encounter)
+    }
+
+    fn track(&self, path: &Path, id: Uuid, contents: &str) {
+        let file = TrackedFile { id, fingerprint: fingerprint(contents) };
+        self.tracked_files().insert(path.to_path_buf(), file);
+    }
+
+    fn tracked_files(&self) -> MutexGuard<'_, HashMap<PathBuf, TrackedFile>> {
+        self.files.lock().expect("encounter file tracking was poisoned")
+    }
+}
+
+// A hidden temporary file per write, so files that differ only by extension never share one
+pub fn write_atomically(path: &Path, contents: &str) -> Result<(), String> {
+    let file_name = path.file_name()
+        .ok_or_else(|| format!("{} is not a file", path.display()))?;
+    let tmp_path = path.with_file_name(format!(".{}.{}.tmp", file_name.to_string_lossy(), Uuid::new_v4()));
+    fs::write(&tmp_path, contents).map_err(|e| e.to_string())?;
+    fs::rename(&tmp_path, path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        e.to_string()
+    })
+}
+
+pub fn fingerprint(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::*;
+    use crate::encounters::Character;
+
+    fn test_encounter(name: &str) -> Encounter {
+        let mut encounter = Encounter::new(name);
+        encounter.add_character(Character::new("Frodo", 8, 18));
+        encounter.add_character(Character::new("Sam", 6, 19));
+        encounter
+    }
+
+    #[test]
+    fn test_new_creates_encounter_directory() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let store = EncounterStore::new(tmp_dir.path()).unwrap();
+        assert_eq!(store.directory, tmp_dir.path().join(ENCOUNTER_DIRECTORY));
+        assert!(store.directory.is_dir());
+    }
+
+    #[test]
+    fn test_new_errors_when_directory_is_a_file() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let bad_path = tmp_dir.path().join(ENCOUNTER_DIRECTORY);
+        File::create(&bad_path).unwrap();
+
+        let result = EncounterStore::new(tmp_dir.path());
+        assert_eq!(result.err(), Some(format!("{} is not a directory", bad_path.display())));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let store = EncounterStore::new(tmp_dir.path()).unwrap();
+        let encounter = test_encounter("Lord of the Rings");
+
+        store.save(&encounter).unwrap();
+        let path = store.path_for(encounter.uuid());
+        assert!(path.exists());
+        assert_eq!(read_dir(&store.directory).unwrap().count(), 1);
+
+        let loaded = store.load(&path).unwrap();
+        assert_eq!(loaded, encounter);
+    }
+
+    #[test]
+    fn test_failed_save_keeps_tracking_the_file_on_disk() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let store = EncounterStore::new(tmp_dir.path()).unwrap();
+        let mut encounter = test_encounter("Lord of the Rings");
+        let path = store.path_for(encounter.uuid());
+        store.save(&encounter).unwrap();
+        let saved = store.tracked(&path);
+
+        fs::remove_dir_all(&store.directory).unwrap();
+        encounter.add_character(Character::new("Pippin", 4, 5));
+        assert!(store.save(&encounter).is_err());
+        assert_eq!(store.tracked(&path), saved);
+
+        let unsaved = test_encounter("Harry Potter");
+        assert!(store.save(&unsaved).is_err());
+        assert_eq!(store.tracked(&store.path_for(unsaved.uuid())), None);
+    }
+
+    #[test]
+    fn test_load_collection() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let store = EncounterStore::new(tmp_dir.path()).unwrap();
+        let mut collection = EncounterCollection::new();
+        collection.add_encounter(test_encounter("Lord of the Rings"));
+        collection.add_encounter(test_encounter("Harry Potter"));
+        collection.encounters.values().for_each(|encounter| store.save(encounter).unwrap());
+
+        // Ignores files that are not encounters and skips encounters that cannot be read
+        File::create(store.directory.join("notes.txt")).unwrap();
+        fs::write(store.directory.join("broken.yaml"), "name: [").unwrap();
+
+        let (loaded, skipped) = store.load_collection().unwrap();
+        assert_eq!(loaded.list_encounters(), collection.list_encounters());
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].message.contains("broken.yaml"));
+    }
+
+    #[test]
+    fn test_write_atomically_next_to_files_with_the_same_stem() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let encounter = tmp_dir.path().join("fellowship.yaml");
+        let checkpoints = tmp_dir.path().join("fellowship.checkpoints");
+
+        write_atomically(&encounter, "encounter").unwrap();
+        write_atomically(&checkpoints, "checkpoints").unwrap();
+        assert_eq!(fs::read_to_string(&encounter).unwrap(), "encounter");
+        assert_eq!(fs::read_to_string(&checkpoints).unwrap(), "checkpoints");
+        assert_eq!(read_dir(tmp_dir.path()).unwrap().count(), 2);
+    }
+
+    #[test]
+    fn test_load_if_changed() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let store = EncounterStore::new(tmp_dir.path()).unwrap();
+        let encounter = test_encounter("Lord of the Rings");
+        let path = store.path_for(encounter.uuid());
+
+        // Our own save is not reported as a change
+        store.save(&encounter).unwrap();
+        assert_eq!(store.load_if_changed(&path).unwrap(), None);
+        assert_eq!(store.tracked(&path).map(|file| file.id), Some(encounter.uuid()));
+
+        let mut edited = encounter.clone();
+        edited.add_character(Character::new("Pippin", 4, 5));
+        fs::write(&path, to_document(&edited).unwrap()).unwrap();
+        assert_eq!(store.load_if_changed(&path).unwrap(), Some(edited));
+        assert_eq!(store.load_if_changed(&path).unwrap(), None);
+
+        assert!(store.untrack(&path).is_some());
+        assert_eq!(store.tracked(&path), None);
+    }
+
+    #[test]
+    fn test_load_external_changes() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let store = EncounterStore::new(tmp_dir.path()).unwrap();
+        let encounter = test_encounter("Lord of the Rings");
+
+        // Nothing to report for encounters that were never written
+        assert_eq!(store.load_external_changes(encounter.uuid()).unwrap(), None);
+
+        store.save(&encounter).unwrap();
+        assert_eq!(store.load_external_changes(encounter.uuid()).unwrap(), None);
+
+        let mut edited = encounter.clone();
+        edited.add_character(Character::new("Pippin", 4, 5));
+        fs::write(store.path_for(encounter.uuid()), to_document(&edited).unwrap()).unwrap();
+        assert_eq!(store.load_external_changes(encounter.uuid()).unwrap(), Some(edited));
+    }
+
+    #[test]
+    fn test_is_encounter_file() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let store = EncounterStore::new(tmp_dir.path()).unwrap();
+
+        assert!(store.is_encounter_file(&store.path_for(Uuid::new_v4())));
+        assert!(!store.is_encounter_file(&store.directory.join("notes.txt")));
+        assert!(!store.is_encounter_file(&tmp_dir.path().join("outside.yaml")));
+    }
+}
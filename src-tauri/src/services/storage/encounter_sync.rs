@@ -0,0 +1,248 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use tauri::{async_runtime, AppHandle, Manager};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::encounters::{Encounter, EncounterCollection};
+use crate::encounters::encounter::EncounterManager;
+use crate::services::FrontendMessage;
+use crate::services::file_system_connection::ArcFileSystemConnection;
+use crate::services::files::{FileChangeEvent, RenameTracker};
+use crate::services::storage::{Autosave, EncounterConflict};
+
+pub const ENCOUNTER_UPDATE_EVENT: &str = "encounter:update";
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum EncounterFileEvent {
+    Added { id: Uuid, name: String },
+    Reloaded { id: Uuid, name: String },
+    Removed { id: Uuid, name: String },
+    Conflicted(Box<EncounterConflict>),
+    Skipped(Vec<FrontendMessage>),
+    SaveFailed { error: String },
+    Invalid { path: String, error: String },
+    JournalCorrupted { path: String, error: String },
+}
+
+impl EncounterFileEvent {
+    pub fn added(encounter: &Encounter) -> Self {
+        Self::Added { id: encounter.uuid(), name: encounter.name().to_string() }
+    }
+
+    pub fn reloaded(encounter: &Encounter) -> Self {
+        Self::Reloaded { id: encounter.uuid(), name: encounter.name().to_string() }
+    }
+
+    pub fn removed(encounter: &Encounter) -> Self {
+        Self::Removed { id: encounter.uuid(), name: encounter.name().to_string() }
+    }
+
+    pub fn invalid(path: &Path, error: String) -> Self {
+        Self::Invalid { path: path.display().to_string(), error }
+    }
+}
+
+pub fn emit_encounter_event(app_handle: &AppHandle, event: EncounterFileEvent) -> Result<(), String> {
+    app_handle.emit_all(ENCOUNTER_UPDATE_EVENT, event).map_err(|e| e.to_string())
+}
+
+pub fn sync_file_change(autosave: &Autosave, collection: &mut EncounterCollection, event: &FileChangeEvent) -> Option<EncounterFileEvent> {
+    match event {
+        FileChangeEvent::Create(data) | FileChangeEvent::Modify(data) => reload_file(autosave, collection, &data.path),
+        FileChangeEvent::Delete(data) => remove_file(autosave, collection, &data.path),
+        FileChangeEvent::Rename { from, to, .. } if autosave.store().is_encounter_file(to) => {
+            autosave.store().untrack(from);
+            reload_file(autosave, collection, to)
+        }
+        FileChangeEvent::Rename { from, .. } => remove_file(autosave, collection, from),
+        FileChangeEvent::RenameAny { .. } | FileChangeEvent::Ignore => None,
+    }
+}
+
+fn reload_file(autosave: &Autosave, collection: &mut EncounterCollection, path: &Path) -> Option<EncounterFileEvent> {
+    let store = autosave.store();
+    if !store.is_encounter_file(path) {
+        return None;
+    }
+    let previous = store.tracked(path);
+    let encounter = match store.load_if_changed(path) {
+        Ok(encounter) => encounter?,
+        Err(e) => return Some(EncounterFileEvent::invalid(path, e)),
+    };
+
+    match autosave.check_external(collection, &encounter) {
+        Ok(Some(conflict)) => return Some(EncounterFileEvent::Conflicted(Box::new(conflict))),
+        Ok(None) => {}
+        Err(e) => return Some(EncounterFileEvent::invalid(path, e)),
+    }
+
+    // The file was overwritten with a different encounter, so the old one is gone
+    if let Some(previous) = previous.filter(|file| file.id != encounter.uuid()) {
+        collection.remove_encounter(previous.id);
+    }
+
+    let event = match collection.find_encounter(encounter.uuid()) {
+        Some(_) => EncounterFileEvent::reloaded(&encounter),
+        None => EncounterFileEvent::added(&encounter),
+    };
+    collection.add_encounter(encounter);
+    Some(event)
+}
+
+fn remove_file(autosave: &Autosave, collection: &mut EncounterCollection, path: &Path) -> Option<EncounterFileEvent> {
+    let file = autosave.store().untrack(path)?;
+    collection.remove_encounter(file.id).map(|encounter| EncounterFileEvent::removed(&encounter))
+}
+
+pub fn watch_encounter_files(connection: ArcFileSystemConnection, manager: EncounterManager, autosave: Autosave) {
+    async_runtime::spawn(async move {
+        let mut receiver = { connection.lock().await.file_watcher.sender.subscribe() };
+
+        let mut renames = RenameTracker::default();
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => renames.track(event),
+                Err(RecvError::Lagged(_)) => None,
+                Err(RecvError::Closed) => break,
+            };
+
+            let change = match event {
+                Some(event) => sync_file_change(&autosave, &mut *manager.lock().await, &event),
+                None => None,
+            };
+
+            if let Some(change) = change {
+                let app_handle = connection.lock().await.app_handle.clone();
+                // The collection is already up to date, so a missed event only delays the frontend's refresh
+                let _ = emit_encounter_event(&app_handle, change);
+            }
+        }
+    });
+}
+
+#[specta::specta]
+pub fn notify_encounter_change(_event: EncounterFileEvent) {}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::encounters::Character;
+    use crate::services::files::FileData;
+    use crate::services::storage::EncounterStore;
+    use crate::services::storage::format::to_document;
+
+    fn setup() -> (tempdir::TempDir, Autosave, EncounterCollection, Encounter) {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let store = EncounterStore::new(tmp_dir.path()).unwrap();
+        let mut encounter = Encounter::new("Lord of the Rings");
+        encounter.add_character(Character::new("Frodo", 8, 18));
+        store.save(&encounter).unwrap();
+        let (collection, _) = store.load_collection().unwrap();
+        (tmp_dir, Autosave::new(store), collection, encounter)
+    }
+
+    #[test]
+    fn test_ignores_own_writes() {
+        let (_tmp_dir, autosave, mut collection, encounter) = setup();
+        let path = autosave.store().path_for(encounter.uuid());
+
+        let event = FileChangeEvent::Modify(FileData::from(path));
+        assert_eq!(sync_file_change(&autosave, &mut collection, &event), None);
+    }
+
+    #[test]
+    fn test_reloads_modified_file() {
+        let (_tmp_dir, autosave, mut collection, mut encounter) = setup();
+        let path = autosave.store().path_for(encounter.uuid());
+
+        encounter.add_character(Character::new("Sam", 6, 19));
+        fs::write(&path, to_document(&encounter).unwrap()).unwrap();
+
+        let event = FileChangeEvent::Modify(FileData::from(path));
+        assert_eq!(sync_file_change(&autosave, &mut collection, &event), Some(EncounterFileEvent::reloaded(&encounter)));
+        assert_eq!(collection.find_encounter(encounter.uuid()), Some(&encounter));
+    }
+
+    #[test]
+    fn test_adds_created_file() {
+        let (_tmp_dir, autosave, mut collection, _) = setup();
+        let encounter = Encounter::new("Harry Potter");
+        let path = autosave.store().path_for(encounter.uuid());
+        fs::write(&path, to_document(&encounter).unwrap()).unwrap();
+
+        let event = FileChangeEvent::Create(FileData::from(path));
+        assert_eq!(sync_file_change(&autosave, &mut collection, &event), Some(EncounterFileEvent::added(&encounter)));
+        assert_eq!(collection.find_encounter(encounter.uuid()), Some(&encounter));
+    }
+
+    #[test]
+    fn test_removes_deleted_file() {
+        let (_tmp_dir, autosave, mut collection, encounter) = setup();
+        let path = autosave.store().path_for(encounter.uuid());
+        fs::remove_file(&path).unwrap();
+
+        let event = FileChangeEvent::Delete(FileData::from(path));
+        assert_eq!(sync_file_change(&autosave, &mut collection, &event), Some(EncounterFileEvent::removed(&encounter)));
+        assert_eq!(collection.find_encounter(encounter.uuid()), None);
+    }
+
+    #[test]
+    fn test_reports_conflicts_with_unsaved_changes() {
+        let (_tmp_dir, autosave, mut collection, encounter) = setup();
+        let path = autosave.store().path_for(encounter.uuid());
+
+        let mut mine = encounter.clone();
+        mine.add_character(Character::new("Sam", 6, 19));
+        collection.add_encounter(mine.clone());
+        autosave.record(&mine).unwrap();
+
+        let mut theirs = encounter.clone();
+        theirs.add_character(Character::new("Pippin", 4, 5));
+        fs::write(&path, to_document(&theirs).unwrap()).unwrap();
+
+        let event = FileChangeEvent::Modify(FileData::from(path));
+        let conflict = EncounterConflict::new(&mine, &theirs);
+        assert_eq!(sync_file_change(&autosave, &mut collection, &event), Some(EncounterFileEvent::Conflicted(Box::new(conflict))));
+        assert_eq!(collection.find_encounter(encounter.uuid()), Some(&mine));
+    }
+
+    #[test]
+    fn test_reports_unreadable_file() {
+        let (_tmp_dir, autosave, mut collection, encounter) = setup();
+        let path = autosave.store().path_for(encounter.uuid());
+        fs::write(&path, "name: [").unwrap();
+
+        let event = FileChangeEvent::Modify(FileData::from(&path));
+        let error = autosave.store().load(&path).unwrap_err();
+        assert_eq!(sync_file_change(&autosave, &mut collection, &event), Some(EncounterFileEvent::invalid(&path, error)));
+        assert_eq!(collection.find_encounter(encounter.uuid()), Some(&encounter));
+    }
+
+    #[test]
+    fn test_renames() {
+        let (_tmp_dir, autosave, mut collection, encounter) = setup();
+        let from = autosave.store().path_for(encounter.uuid());
+
+        // Renaming within the encounter directory keeps the encounter
+        let to = autosave.store().directory().join("fellowship.yaml");
+        fs::rename(&from, &to).unwrap();
+        let event = FileChangeEvent::Rename { from, to: to.clone(), data: FileData::from(&to) };
+        assert_eq!(sync_file_change(&autosave, &mut collection, &event), Some(EncounterFileEvent::reloaded(&encounter)));
+        assert_eq!(collection.find_encounter(encounter.uuid()), Some(&encounter));
+        assert_eq!(autosave.store().file_for(encounter.uuid()), to);
+
+        // Renaming it to something that is not an encounter removes it
+        let from = to;
+        let to = autosave.store().directory().join("fellowship.txt");
+        fs::rename(&from, &to).unwrap();
+        let event = FileChangeEvent::Rename { from, to: to.clone(), data: FileData::from(&to) };
+        assert_eq!(sync_file_change(&autosave, &mut collection, &event), Some(EncounterFileEvent::removed(&encounter)));
+        assert_eq!(collection.find_encounter(encounter.uuid()), None);
+    }
+}
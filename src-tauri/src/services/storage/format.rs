@@ -0,0 +1,230 @@
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+
+use crate::encounters::Encounter;
+
+type Migration = fn(Value) -> Result<Value, String>;
+
+// `MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`
+const MIGRATIONS: &[Migration] = &[
+    upgrade_bare_encounter,
+];
+
+pub const FORMAT_VERSION: u64 = MIGRATIONS.len() as u64;
+
+#[derive(Serialize)]
+struct EncounterDocument<'a> {
+    version: u64,
+    encounter: &'a Encounter,
+}
+
+pub fn to_document(encounter: &Encounter) -> Result<String, String> {
+    let document = EncounterDocument { version: FORMAT_VERSION, encounter };
+    serde_yaml::to_string(&document).map_err(|e| e.to_string())
+}
+
+pub fn from_document(contents: &str) -> Result<Encounter, String> {
+    let document: Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+    let document = migrate(document)?;
+    let encounter = document.get("encounter").cloned().ok_or("Encounter file has no encounter")?;
+    serde_yaml::from_value(encounter).map_err(|e| e.to_string())
+}
+
+pub fn migrate(mut document: Value) -> Result<Value, String> {
+    let version = document_version(&document)?;
+    if version > FORMAT_VERSION {
+        return Err(format!(
+            "Encounter file uses format version {}, but this version of Encounter Manager only understands up to version {}",
+            version, FORMAT_VERSION
+        ));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        document = migration(document)?;
+    }
+    Ok(document)
+}
+
+pub fn upgrade_encounter(version: u64, encounter: Value) -> Result<Value, String> {
+    let document = match version {
+        0 => encounter,
+        _ => {
+            let mut document = Value::Mapping(Mapping::new());
+            set_version(&mut document, version)?;
+            document.as_mapping_mut()
+                .ok_or("Encounter file is not a mapping")?
+                .insert(Value::from("encounter"), encounter);
+            document
+        }
+    };
+    migrate(document)?.get("encounter").cloned().ok_or(String::from("Encounter file has no encounter"))
+}
+
+pub fn document_version(document: &Value) -> Result<u64, String> {
+    match document.get("version") {
+        Some(version) => version.as_u64().ok_or(format!("Invalid encounter format version {:?}", version)),
+        // Files written before the format was versioned hold a bare encounter
+        None => Ok(0),
+    }
+}
+
+fn set_version(document: &mut Value, version: u64) -> Result<(), String> {
+    let mapping = document.as_mapping_mut().ok_or("Encounter file is not a mapping")?;
+    mapping.insert(Value::from("version"), Value::from(version));
+    Ok(())
+}
+
+fn encounter_mapping(document: &mut Value) -> Result<&mut Mapping, String> {
+    document.get_mut("encounter")
+        .and_then(Value::as_mapping_mut)
+        .ok_or(String::from("Encounter file has no encounter"))
+}
+
+fn insert_missing(mapping: &mut Mapping, key: &str, value: Value) {
+    if !mapping.contains_key(key) {
+        mapping.insert(Value::from(key), value);
+    }
+}
+
+fn upgrade_bare_encounter(encounter: Value) -> Result<Value, String> {
+    let mut document = Value::Mapping(Mapping::new());
+    set_version(&mut document, 1)?;
+    document.as_mapping_mut()
+        .ok_or("Encounter file is not a mapping")?
+        .insert(Value::from("encounter"), encounter);
+
+    let encounter = encounter_mapping(&mut document)?;
+    // A running encounter is placed in round 1, where everyone ahead of the active character has acted
+    let current = [encounter.get("activeCharacter"), encounter.get("lastActiveCharacter")]
+        .into_iter()
+        .flatten()
+        .find(|id| !id.is_null())
+        .cloned();
+    let characters = encounter.get("characters").and_then(Value::as_sequence).cloned().unwrap_or_default();
+    let turn = current.as_ref()
+        .and_then(|id| characters.iter().position(|c| c.get("id") == Some(id)))
+        .unwrap_or(0);
+    let acted: Vec<Value> = match current {
+        Some(_) => characters.iter().take(turn).filter_map(|c| c.get("id").cloned()).collect(),
+        None => Vec::new(),
+    };
+
+    insert_missing(encounter, "round", Value::from(if current.is_some() { 1 } else { 0 }));
+    insert_missing(encounter, "turn", Value::from(turn as u64));
+    insert_missing(encounter, "acted", Value::Sequence(acted));
+    insert_missing(encounter, "tieBreak", Value::from("dexterity"));
+    insert_missing(encounter, "manualOrder", Value::from(false));
+    insert_missing(encounter, "surprise", Value::from(false));
+    insert_missing(encounter, "surpriseRound", Value::from(false));
+    for key in ["delayed", "readied", "skipped", "expired", "passedTurns", "groups", "initiativeEntries", "concentrationChecks", "log"] {
+        insert_missing(encounter, key, Value::Sequence(Vec::new()));
+    }
+
+    for character in characters_mut(&mut document)? {
+        // Only a missing initiative could be 0
+        let initiative = character.get("initiative").and_then(Value::as_i64).unwrap_or(0);
+        let mut scores = Mapping::new();
+        for ability in ["strength", "dexterity", "constitution", "intelligence", "wisdom", "charisma"] {
+            scores.insert(Value::from(ability), Value::from(10));
+        }
+        let mut saves = Mapping::new();
+        saves.insert(Value::from("successes"), Value::from(0));
+        saves.insert(Value::from("failures"), Value::from(0));
+
+        insert_missing(character, "initiativeSet", Value::from(initiative != 0));
+        insert_missing(character, "player", Value::from(false));
+        insert_missing(character, "armorClass", Value::from(10));
+        insert_missing(character, "abilityScores", Value::Mapping(scores));
+        insert_missing(character, "proficiencyBonus", Value::from(2));
+        insert_missing(character, "tieBreaker", Value::Null);
+        insert_missing(character, "lifeState", Value::from("alive"));
+        insert_missing(character, "deathSaves", Value::Mapping(saves));
+        insert_missing(character, "concentration", Value::Null);
+        for key in ["surprised", "skipTurn"] {
+            insert_missing(character, key, Value::from(false));
+        }
+        for key in ["saveProficiencies", "conditions", "resistances", "vulnerabilities", "immunities"] {
+            insert_missing(character, key, Value::Sequence(Vec::new()));
+        }
+    }
+    Ok(document)
+}
+
+fn characters_mut(document: &mut Value) -> Result<Vec<&mut Mapping>, String> {
+    encounter_mapping(document)?
+        .get_mut("characters")
+        .and_then(Value::as_sequence_mut)
+        .ok_or("Encounter has no characters")?
+        .iter_mut()
+        .map(|character| character.as_mapping_mut().ok_or(String::from("Character is not a mapping")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::encounters::Character;
+
+    const FIXTURES: [&str; 2] = [
+        include_str!("../../../fixtures/encounters/v0.yaml"),
+        include_str!("../../../fixtures/encounters/v1.yaml"),
+    ];
+
+    #[test]
+    fn test_fixture_for_every_version() {
+        assert_eq!(FIXTURES.len() as u64, FORMAT_VERSION + 1);
+    }
+
+    #[test]
+    fn test_migrates_every_fixture() {
+        let frodo = Uuid::parse_str("7d3c5a7e-0c4f-4a6e-9a51-2f1c1f0b8e01").unwrap();
+        for (version, fixture) in FIXTURES.iter().enumerate() {
+            let encounter = from_document(fixture)
+                .unwrap_or_else(|e| panic!("version {} fixture failed to load: {}", version, e));
+
+            assert_eq!(encounter.id(), "c0f0e8a4-5d3b-4b1e-8d6a-3c2b1a0f9e77");
+            assert_eq!(encounter.name(), "Lord of the Rings");
+            assert_eq!(encounter.get_characters().len(), 2);
+            assert_eq!(encounter.get_active_character_id(), Some(frodo));
+            let character = encounter.find_character(frodo.to_string()).unwrap();
+            assert_eq!(character.hp.current, 5);
+            assert_eq!(character.hp.temporary, 2);
+            assert_eq!(character.player, version > 0);
+            assert_eq!(character.ability_scores.dexterity, if version > 0 { 14 } else { 10 });
+            assert_eq!(character.conditions.len(), if version > 0 { 1 } else { 0 });
+            assert!(character.has_initiative());
+            assert_eq!(encounter.log().len(), if version > 0 { 2 } else { 0 });
+            assert_eq!(encounter.round(), 1);
+            let document = serde_yaml::to_value(&encounter).unwrap();
+            assert_eq!(document["turn"], 1);
+            assert_eq!(document["acted"], serde_yaml::from_str::<Value>("[3b9f2d41-8e6a-4c0d-b7f5-9a8e7d6c5b02]").unwrap());
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut encounter = Encounter::new("Harry Potter");
+        encounter.add_character(Character::new("Harry", 4, 18));
+
+        let document = to_document(&encounter).unwrap();
+        assert!(document.starts_with(&format!("version: {}\n", FORMAT_VERSION)));
+        assert_eq!(from_document(&document).unwrap(), encounter);
+    }
+
+    #[test]
+    fn test_rejects_newer_versions() {
+        let document = format!("version: {}\nencounter: {{}}\n", FORMAT_VERSION + 1);
+        let result = from_document(&document);
+        assert_eq!(result.err(), Some(format!(
+            "Encounter file uses format version {}, but this version of Encounter Manager only understands up to version {}",
+            FORMAT_VERSION + 1, FORMAT_VERSION
+        )));
+    }
+
+    #[test]
+    fn test_rejects_invalid_versions() {
+        assert!(from_document("version: two\nencounter: {}\n").is_err());
+    }
+}
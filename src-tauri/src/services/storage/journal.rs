@@ -0,0 +1,308 @@
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::encounters::{Encounter, EncounterCollection};
+use crate::services::storage::write_atomically;
+use crate::services::storage::format::{from_document, to_document};
+
+pub const JOURNAL_FILE: &str = ".journal";
+pub const CORRUPT_JOURNAL_FILE: &str = ".journal.corrupt";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JournalEntry {
+    Updated(Box<Encounter>),
+    Deleted(Uuid),
+}
+
+// Encounters are journaled as versioned documents, so entries written by an older version still migrate
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum StoredEntry {
+    Updated(String),
+    Deleted(Uuid),
+}
+
+impl JournalEntry {
+    pub fn encounter_id(&self) -> Uuid {
+        match self {
+            JournalEntry::Updated(encounter) => encounter.uuid(),
+            JournalEntry::Deleted(id) => *id,
+        }
+    }
+
+    pub fn apply(self, collection: &mut EncounterCollection) {
+        match self {
+            JournalEntry::Updated(encounter) => collection.add_encounter(*encounter),
+            JournalEntry::Deleted(id) => {
+                collection.remove_encounter(id);
+            }
+        }
+    }
+
+    fn to_line(&self) -> Result<String, String> {
+        let stored = match self {
+            JournalEntry::Updated(encounter) => StoredEntry::Updated(to_document(encounter)?),
+            JournalEntry::Deleted(id) => StoredEntry::Deleted(*id),
+        };
+        let mut line = serde_json::to_string(&stored).map_err(|e| e.to_string())?;
+        line.push('\n');
+        Ok(line)
+    }
+
+    fn from_line(line: &str) -> Result<Self, String> {
+        match serde_json::from_str(line).map_err(|e| e.to_string())? {
+            StoredEntry::Updated(document) => Ok(JournalEntry::Updated(Box::new(from_document(&document)?))),
+            StoredEntry::Deleted(id) => Ok(JournalEntry::Deleted(id)),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new(directory: &Path) -> Self {
+        Self {
+            path: directory.join(JOURNAL_FILE),
+        }
+    }
+
+    pub fn append(&self, entry: &JournalEntry) -> Result<(), String> {
+        let line = entry.to_line()?;
+        self.drop_partial_entry()?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| e.to_string())?;
+        file.write_all(line.as_bytes()).map_err(|e| e.to_string())?;
+        file.sync_data().map_err(|e| e.to_string())
+    }
+
+    // Only a last entry cut short by a crash is tolerated
+    pub fn read(&self) -> Result<Vec<JournalEntry>, String> {
+        let (entries, error) = self.read_entries()?;
+        match error {
+            Some(error) => Err(error),
+            None => Ok(entries),
+        }
+    }
+
+    pub fn repair(&self) -> Result<Option<String>, String> {
+        let (entries, error) = self.read_entries()?;
+        if error.is_some() {
+            fs::rename(&self.path, self.corrupt_path()).map_err(|e| e.to_string())?;
+            self.write(&entries)?;
+        }
+        Ok(error)
+    }
+
+    pub fn corrupt_path(&self) -> PathBuf {
+        self.path.with_file_name(CORRUPT_JOURNAL_FILE)
+    }
+
+    // Keeps reading past corrupt entries, and returns the first problem along with everything that parsed
+    fn read_entries(&self) -> Result<(Vec<JournalEntry>, Option<String>), String> {
+        if !self.path.exists() {
+            return Ok((Vec::new(), None));
+        }
+        let contents = fs::read_to_string(&self.path).map_err(|e| e.to_string())?;
+        let lines: Vec<(usize, &str)> = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .collect();
+
+        let mut entries = Vec::new();
+        let mut error = None;
+        for (index, (number, line)) in lines.iter().enumerate() {
+            match JournalEntry::from_line(line) {
+                Ok(entry) => entries.push(entry),
+                Err(_) if index == lines.len() - 1 && !contents.ends_with('\n') => {}
+                Err(e) => {
+                    error.get_or_insert(format!("Journal entry on line {} is corrupt: {}", number + 1, e));
+                }
+            }
+        }
+        Ok((entries, error))
+    }
+
+    pub fn retain<F: FnMut(&JournalEntry) -> bool>(&self, keep: F) -> Result<(), String> {
+        let entries: Vec<JournalEntry> = self.read()?.into_iter().filter(keep).collect();
+        self.write(&entries)
+    }
+
+    fn write(&self, entries: &[JournalEntry]) -> Result<(), String> {
+        if entries.is_empty() {
+            return self.clear();
+        }
+
+        let mut contents = String::new();
+        for entry in entries {
+            contents.push_str(&entry.to_line()?);
+        }
+        write_atomically(&self.path, &contents)
+    }
+
+    fn drop_partial_entry(&self) -> Result<(), String> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        let contents = fs::read_to_string(&self.path).map_err(|e| e.to_string())?;
+        if contents.is_empty() || contents.ends_with('\n') {
+            return Ok(());
+        }
+        let end = contents.rfind('\n').map_or(0, |index| index + 1);
+        OpenOptions::new()
+            .write(true)
+            .open(&self.path)
+            .and_then(|file| file.set_len(end as u64))
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        if !self.path.exists() {
+            return Ok(());
+        }
+        fs::remove_file(&self.path).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encounters::Character;
+
+    #[test]
+    fn test_append_and_read() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let journal = Journal::new(tmp_dir.path());
+        assert_eq!(journal.read().unwrap(), vec![]);
+
+        let mut encounter = Encounter::new("Lord of the Rings");
+        journal.append(&JournalEntry::Updated(Box::new(encounter.clone()))).unwrap();
+        encounter.add_character(Character::new("Frodo", 8, 18));
+        journal.append(&JournalEntry::Updated(Box::new(encounter.clone()))).unwrap();
+
+        let entries = journal.read().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1], JournalEntry::Updated(Box::new(encounter)));
+    }
+
+    #[test]
+    fn test_read_migrates_entries() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let journal = Journal::new(tmp_dir.path());
+        let document = include_str!("../../../fixtures/encounters/v0.yaml");
+        let line = serde_json::to_string(&StoredEntry::Updated(document.to_string())).unwrap();
+        fs::write(tmp_dir.path().join(JOURNAL_FILE), line + "\n").unwrap();
+
+        assert_eq!(journal.read().unwrap(), vec![JournalEntry::Updated(Box::new(from_document(document).unwrap()))]);
+    }
+
+    #[test]
+    fn test_read_skips_partial_entries() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let journal = Journal::new(tmp_dir.path());
+        let encounter = Encounter::new("Lord of the Rings");
+        journal.append(&JournalEntry::Updated(Box::new(encounter.clone()))).unwrap();
+
+        let mut file = OpenOptions::new().append(true).open(tmp_dir.path().join(JOURNAL_FILE)).unwrap();
+        file.write_all(br#"{"updated":{"id":"#).unwrap();
+
+        assert_eq!(journal.read().unwrap(), vec![JournalEntry::Updated(Box::new(encounter.clone()))]);
+
+        // The next append starts on a fresh line instead of gluing onto the partial one
+        journal.append(&JournalEntry::Deleted(encounter.uuid())).unwrap();
+        assert_eq!(journal.read().unwrap(), vec![
+            JournalEntry::Updated(Box::new(encounter.clone())),
+            JournalEntry::Deleted(encounter.uuid()),
+        ]);
+    }
+
+    #[test]
+    fn test_read_fails_on_corrupt_entries() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let journal = Journal::new(tmp_dir.path());
+        let encounter = Encounter::new("Lord of the Rings");
+        journal.append(&JournalEntry::Updated(Box::new(encounter.clone()))).unwrap();
+
+        let mut file = OpenOptions::new().append(true).open(tmp_dir.path().join(JOURNAL_FILE)).unwrap();
+        file.write_all(b"not an entry\n").unwrap();
+        assert!(journal.read().unwrap_err().starts_with("Journal entry on line 2 is corrupt"));
+
+        // A complete line that does not parse is not a partial append either
+        journal.clear().unwrap();
+        fs::write(tmp_dir.path().join(JOURNAL_FILE), "not an entry\n").unwrap();
+        assert!(journal.read().is_err());
+    }
+
+    #[test]
+    fn test_repair_keeps_readable_entries() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let journal = Journal::new(tmp_dir.path());
+        let first = Encounter::new("Lord of the Rings");
+        let second = Encounter::new("Harry Potter");
+        journal.append(&JournalEntry::Updated(Box::new(first.clone()))).unwrap();
+        let mut file = OpenOptions::new().append(true).open(tmp_dir.path().join(JOURNAL_FILE)).unwrap();
+        file.write_all(b"not an entry\n").unwrap();
+        journal.append(&JournalEntry::Updated(Box::new(second.clone()))).unwrap();
+        let corrupt = fs::read_to_string(tmp_dir.path().join(JOURNAL_FILE)).unwrap();
+
+        assert!(journal.repair().unwrap().unwrap().starts_with("Journal entry on line 2 is corrupt"));
+        assert_eq!(fs::read_to_string(journal.corrupt_path()).unwrap(), corrupt);
+        assert_eq!(journal.read().unwrap(), vec![
+            JournalEntry::Updated(Box::new(first)),
+            JournalEntry::Updated(Box::new(second)),
+        ]);
+        assert_eq!(journal.repair().unwrap(), None);
+    }
+
+    #[test]
+    fn test_clear() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let journal = Journal::new(tmp_dir.path());
+        journal.append(&JournalEntry::Updated(Box::new(Encounter::new("Harry Potter")))).unwrap();
+
+        journal.clear().unwrap();
+        assert_eq!(journal.read().unwrap(), vec![]);
+        assert!(journal.clear().is_ok());
+    }
+
+    #[test]
+    fn test_retain() {
+        let tmp_dir = tempdir::TempDir::new("tempdir").unwrap();
+        let journal = Journal::new(tmp_dir.path());
+        let kept = Encounter::new("Lord of the Rings");
+        journal.append(&JournalEntry::Updated(Box::new(Encounter::new("Harry Potter")))).unwrap();
+        journal.append(&JournalEntry::Updated(Box::new(kept.clone()))).unwrap();
+
+        journal.retain(|entry| entry.encounter_id() == kept.uuid()).unwrap();
+        assert_eq!(journal.read().unwrap(), vec![JournalEntry::Updated(Box::new(kept))]);
+
+        journal.retain(|_| false).unwrap();
+        assert!(!tmp_dir.path().join(JOURNAL_FILE).exists());
+    }
+
+    #[test]
+    fn test_apply_replaces_encounter() {
+        let mut collection = EncounterCollection::new();
+        let mut encounter = Encounter::new("Lord of the Rings");
+        collection.add_encounter(encounter.clone());
+
+        encounter.add_character(Character::new("Frodo", 8, 18));
+        JournalEntry::Updated(Box::new(encounter.clone())).apply(&mut collection);
+
+        assert_eq!(collection.find_encounter(encounter.uuid()), Some(&encounter));
+
+        JournalEntry::Deleted(encounter.uuid()).apply(&mut collection);
+        assert_eq!(collection.find_encounter(encounter.uuid()), None);
+    }
+}
@@ -0,0 +1,14 @@
+pub mod autosave;
+pub mod checkpoints;
+pub mod conflicts;
+pub mod encounter_store;
+pub mod encounter_sync;
+pub mod format;
+pub mod journal;
+
+pub use autosave::*;
+pub use checkpoints::*;
+pub use conflicts::*;
+pub use encounter_store::*;
+pub use encounter_sync::*;
+pub use journal::*;